@@ -1,10 +1,18 @@
-use bevy::prelude::*;
+use std::f32::consts::PI;
+
+use bevy::{gltf::GltfExtras, prelude::*, utils::HashSet};
 use bevy_mod_billboard::{BillboardTextBundle, BillboardTextureBundle};
 use bevy_mod_outline::{OutlineBundle, OutlineVolume};
+use bevy_xpbd_3d::prelude::{
+    Collider, CollisionEnded, CollisionStarted, LinearVelocity, RigidBody, Sensor,
+};
+use serde::Deserialize;
 
 use crate::{
     controller::CharacterController,
-    health::{Health, UpdateHealth},
+    health::{DamageEvent, DamageKind, HealEvent, Health},
+    projectile::spawn_bolt,
+    weapon::{Ammo, DamageBuff},
     Enemy, GameState, InGame,
 };
 
@@ -12,17 +20,31 @@ pub struct BeaconPlugin;
 
 impl Plugin for BeaconPlugin {
     fn build(&self, app: &mut App) {
+        app.add_event::<BeaconActivated>()
+            .add_event::<BeaconOnline>()
+            .add_event::<BeaconInterrupted>()
+            .add_event::<PlayerEnteredSafeZone>()
+            .add_event::<PlayerLeftSafeZone>();
+
         app.add_systems(
             Update,
             (
                 (
-                    clear_enemies_in_safezone,
+                    spawn_safe_zones,
+                    despawn_safe_zones,
+                    update_safe_zone_occupants,
+                    apply_safe_zone_responses,
                     heal_player_in_safezone,
+                    apply_beacon_effects,
+                    arm_beacon_turrets,
+                    fire_beacon_turrets,
                     activate_beacon,
+                    interrupt_beacons,
                     update_activation_timers,
                     show_beacons,
                     highlight_nearby_beacons,
                     update_beacon_ui,
+                    play_beacon_sounds,
                 )
                     .chain()
                     .run_if(in_state(GameState::Playing)),
@@ -30,15 +52,131 @@ impl Plugin for BeaconPlugin {
             ),
         )
         .add_systems(OnEnter(GameState::BeginGame), spawn_beacon_ui)
-        .add_systems(OnEnter(GameState::GameOver), crate::cleanup::<Beacon>);
+        .add_systems(OnEnter(GameState::GameOver), crate::cleanup::<Beacon>)
+        .add_systems(OnEnter(GameState::MainMenu), crate::cleanup::<Beacon>);
     }
 }
 
-#[derive(Component, Default)]
+/// A beacon's definition. Levels can override any of it through the extras of the beacon's node,
+/// e.g. `{ "activation_time": 20.0, "effects": [{ "type": "ammo_refill" }] }`.
+#[derive(Component, Deserialize, Clone, Debug)]
+#[serde(default)]
 pub struct Beacon {
     pub activation_radius: f32,
     pub safe_zone_radius: f32,
+    /// The health restored to the player each second while in the safe zone.
     pub heal_factor: f32,
+    /// How long the player has to hold the beacon to bring it online, in seconds.
+    pub activation_time: f32,
+    /// What the beacon does once online, on top of healing the player.
+    pub effects: Vec<BeaconEffect>,
+    /// The ids of the portals this beacon helps close, see [`PortalId`](crate::portal::PortalId).
+    pub portals: Vec<u32>,
+    /// What the safe zone does to enemies inside it.
+    pub enemy_response: EnemyResponse,
+}
+
+impl Default for Beacon {
+    fn default() -> Self {
+        Self {
+            activation_radius: 5.0,
+            safe_zone_radius: 10.0,
+            heal_factor: 5.0,
+            activation_time: 15.0,
+            effects: Vec::new(),
+            portals: Vec::new(),
+            enemy_response: EnemyResponse::default(),
+        }
+    }
+}
+
+impl Beacon {
+    /// Reads a beacon's definition from the extras of its level node, falling back to the
+    /// defaults for anything left out.
+    pub fn from_extras(extras: Option<&GltfExtras>) -> Self {
+        let Some(extras) = extras else {
+            return Self::default();
+        };
+
+        let mut beacon: Self = serde_json::from_str(&extras.value).unwrap_or_else(|err| {
+            warn!("invalid beacon definition {:?}: {err}", extras.value);
+            Self::default()
+        });
+
+        // both of these end up as timer durations, which panic when they aren't positive.
+        if !(beacon.activation_time.is_finite() && beacon.activation_time > 0.0) {
+            warn!(
+                "invalid beacon activation time {}, using the default",
+                beacon.activation_time
+            );
+            beacon.activation_time = Self::default().activation_time;
+        }
+
+        for effect in beacon.effects.iter_mut() {
+            if let BeaconEffect::Turret { firing_rate, .. } = effect {
+                if !(firing_rate.is_finite() && *firing_rate > 0.0) {
+                    warn!("invalid beacon turret firing rate {firing_rate}, using the default");
+                    *firing_rate = DEFAULT_TURRET_FIRING_RATE;
+                }
+            }
+        }
+
+        beacon
+    }
+}
+
+/// How many times a minute a beacon turret fires when its level gives it a bad firing rate.
+const DEFAULT_TURRET_FIRING_RATE: f32 = 60.0;
+
+/// An extra effect an online beacon has.
+#[derive(Deserialize, Clone, Debug)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum BeaconEffect {
+    /// Refills the ammo of the player's weapons while they're in the safe zone.
+    AmmoRefill,
+    /// Multiplies the damage the player deals while they're in the safe zone.
+    DamageBuff { multiplier: f32 },
+    /// Shoots at enemies within `range` of the beacon, `firing_rate` times a minute.
+    Turret {
+        damage: f32,
+        range: f32,
+        firing_rate: f32,
+    },
+}
+
+/// What a beacon's safe zone does to enemies inside it.
+#[derive(Deserialize, Clone, Copy, Debug, Default)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum EnemyResponse {
+    /// Pushes enemies back out of the zone.
+    Repel { strength: f32 },
+    /// Caps how fast enemies can move inside the zone.
+    Slow { max_speed: f32 },
+    /// Damages enemies for every second they spend inside the zone.
+    DamageOverTime { damage_per_second: f32 },
+    /// Kills enemies as soon as they enter the zone.
+    #[default]
+    InstantKill,
+}
+
+/// The safe zone around an online beacon. It's a sensor, so what's inside of it is tracked through
+/// collision events instead of checking the distance to everything every frame.
+#[derive(Component)]
+pub struct SafeZone {
+    pub beacon: Entity,
+    /// The player and enemies currently inside the zone.
+    pub occupants: HashSet<Entity>,
+}
+
+/// How tall a safe zone is, centered on its beacon.
+const SAFE_ZONE_HEIGHT: f32 = 8.0;
+
+/// A turret mounted on a beacon, which only fires while the beacon is online.
+#[derive(Component)]
+pub struct BeaconTurret {
+    pub damage: f32,
+    pub range: f32,
+    pub timer: Timer,
 }
 
 #[derive(Component, PartialEq, Eq, Copy, Clone, Hash, Debug, Default)]
@@ -49,39 +187,205 @@ pub enum BeaconState {
     Online,
 }
 
+impl BeaconState {
+    /// The color beacons in this state are shown in.
+    pub fn color(&self) -> Color {
+        match self {
+            BeaconState::Offline => Color::RED,
+            BeaconState::Activated => Color::ORANGE,
+            BeaconState::Online => Color::GREEN,
+        }
+    }
+}
+
+/// Sent when the player starts activating a beacon.
+#[derive(Event)]
+pub struct BeaconActivated(pub Entity);
+
+/// Sent when a beacon finishes activating and comes online.
+#[derive(Event)]
+pub struct BeaconOnline(pub Entity);
+
+/// Sent when enemies knock a beacon back offline, whether it was being activated or already online.
+#[derive(Event)]
+pub struct BeaconInterrupted {
+    pub beacon: Entity,
+    pub reason: InterruptReason,
+}
+
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub enum InterruptReason {
+    /// Enemies destroyed the beacon, either during activation or once it was online.
+    Destroyed,
+    /// The player left the beacon until its progress drained away.
+    Abandoned,
+}
+
+/// Sent when the player steps into the safe zone of an online beacon.
+#[derive(Event)]
+pub struct PlayerEnteredSafeZone(pub Entity);
+
+/// Sent when the player steps out of the safe zone of an online beacon.
+#[derive(Event)]
+pub struct PlayerLeftSafeZone(pub Entity);
+
 #[derive(Component)]
 pub struct ActivationCountdown;
 
 #[derive(Component)]
 pub struct ActivationTimer(pub Timer);
 
-impl Default for ActivationTimer {
-    fn default() -> Self {
-        Self(Timer::from_seconds(15.0, TimerMode::Once))
+impl ActivationTimer {
+    pub fn from_seconds(duration: f32) -> Self {
+        Self(Timer::from_seconds(duration, TimerMode::Once))
     }
 }
 
+/// The number shown on a beacon's countdown with `remaining` of its activation left.
+fn countdown_text(remaining: std::time::Duration) -> String {
+    (remaining.as_secs_f32().ceil() as u32).to_string()
+}
+
 #[derive(Component, Default)]
 pub struct BeaconBundle {
     pub beacon: Beacon,
     pub beacon_state: BeaconState,
 }
 
-fn clear_enemies_in_safezone(
-    mut writer: EventWriter<UpdateHealth>,
-    enemies: Query<(Entity, &Transform, &Health), With<Enemy>>,
-    beacons: Query<(&Transform, &Beacon, &BeaconState), Without<Enemy>>,
+fn spawn_safe_zones(
+    mut commands: Commands,
+    mut online: EventReader<BeaconOnline>,
+    beacons: Query<(&Transform, &Beacon)>,
+) {
+    for BeaconOnline(entity) in online.read() {
+        let Ok((transform, beacon)) = beacons.get(*entity) else {
+            continue;
+        };
+
+        commands.spawn((
+            Sensor,
+            RigidBody::Static,
+            Collider::cylinder(SAFE_ZONE_HEIGHT, beacon.safe_zone_radius),
+            SpatialBundle::from_transform(Transform::from_translation(transform.translation)),
+            SafeZone {
+                beacon: *entity,
+                occupants: HashSet::new(),
+            },
+            InGame,
+        ));
+    }
+}
+
+/// Removes the safe zones of beacons that are no longer online.
+fn despawn_safe_zones(
+    mut commands: Commands,
+    zones: Query<(Entity, &SafeZone)>,
+    beacons: Query<&BeaconState>,
+    players: Query<(), With<CharacterController>>,
+    mut left: EventWriter<PlayerLeftSafeZone>,
 ) {
-    for (transform, beacon, _) in beacons
-        .iter()
-        .filter(|(_, _, state)| **state == BeaconState::Online)
-    {
-        for (entity, enemy_transform, health) in enemies.iter() {
-            let beacon_pos = transform.translation;
-            let enemy_pos = enemy_transform.translation;
+    for (entity, zone) in zones.iter() {
+        if beacons
+            .get(zone.beacon)
+            .is_ok_and(|state| *state == BeaconState::Online)
+        {
+            continue;
+        }
+
+        if zone.occupants.iter().any(|occupant| players.contains(*occupant)) {
+            left.send(PlayerLeftSafeZone(zone.beacon));
+        }
+
+        commands.entity(entity).despawn_recursive();
+    }
+}
+
+fn update_safe_zone_occupants(
+    mut started: EventReader<CollisionStarted>,
+    mut ended: EventReader<CollisionEnded>,
+    mut zones: Query<&mut SafeZone>,
+    occupants: Query<(), Or<(With<CharacterController>, With<Enemy>)>>,
+    players: Query<(), With<CharacterController>>,
+    mut entered: EventWriter<PlayerEnteredSafeZone>,
+    mut left: EventWriter<PlayerLeftSafeZone>,
+) {
+    for CollisionStarted(a, b) in started.read() {
+        for (zone, other) in [(*a, *b), (*b, *a)] {
+            let Ok(mut zone) = zones.get_mut(zone) else {
+                continue;
+            };
+
+            if occupants.contains(other) && zone.occupants.insert(other) && players.contains(other) {
+                entered.send(PlayerEnteredSafeZone(zone.beacon));
+            }
+        }
+    }
 
-            if beacon_pos.distance(enemy_pos) <= beacon.safe_zone_radius {
-                writer.send(UpdateHealth(entity, -health.amount));
+    for CollisionEnded(a, b) in ended.read() {
+        for (zone, other) in [(*a, *b), (*b, *a)] {
+            let Ok(mut zone) = zones.get_mut(zone) else {
+                continue;
+            };
+
+            if zone.occupants.remove(&other) && players.contains(other) {
+                left.send(PlayerLeftSafeZone(zone.beacon));
+            }
+        }
+    }
+
+    // enemies that die inside a zone never leave it.
+    for mut zone in zones.iter_mut() {
+        if zone.occupants.iter().any(|occupant| !occupants.contains(*occupant)) {
+            zone.occupants.retain(|occupant| occupants.contains(*occupant));
+        }
+    }
+}
+
+fn apply_safe_zone_responses(
+    time: Res<Time>,
+    zones: Query<&SafeZone>,
+    beacons: Query<(&Transform, &Beacon), Without<Enemy>>,
+    mut enemies: Query<(&Transform, &mut LinearVelocity), With<Enemy>>,
+    mut writer: EventWriter<DamageEvent>,
+) {
+    for zone in zones.iter() {
+        let Ok((transform, beacon)) = beacons.get(zone.beacon) else {
+            continue;
+        };
+
+        for occupant in zone.occupants.iter() {
+            let Ok((enemy_transform, mut lin_vel)) = enemies.get_mut(*occupant) else {
+                continue;
+            };
+
+            match beacon.enemy_response {
+                EnemyResponse::Repel { strength } => {
+                    let away = enemy_transform.translation - transform.translation;
+                    let away = Vec3::new(away.x, 0.0, away.z).normalize_or_zero();
+
+                    lin_vel.0 += away * strength * time.delta_seconds();
+                }
+                EnemyResponse::Slow { max_speed } => {
+                    let horizontal = Vec3::new(lin_vel.x, 0.0, lin_vel.z).clamp_length_max(max_speed);
+
+                    lin_vel.x = horizontal.x;
+                    lin_vel.z = horizontal.z;
+                }
+                EnemyResponse::DamageOverTime { damage_per_second } => {
+                    writer.send(
+                        DamageEvent::new(
+                            *occupant,
+                            damage_per_second * time.delta_seconds(),
+                            DamageKind::Environmental,
+                        )
+                        .with_source(zone.beacon),
+                    );
+                }
+                EnemyResponse::InstantKill => {
+                    writer.send(
+                        DamageEvent::new(*occupant, 0.0, DamageKind::Lethal).with_source(zone.beacon),
+                    );
+                }
             }
         }
     }
@@ -97,73 +401,229 @@ impl Default for HealZoneTimer {
 
 fn heal_player_in_safezone(
     time: Res<Time>,
-    players: Query<(Entity, &Transform), With<CharacterController>>,
-    beacons: Query<(&Transform, &Beacon, &BeaconState), Without<CharacterController>>,
+    zones: Query<&SafeZone>,
+    beacons: Query<&Beacon>,
+    players: Query<Entity, With<CharacterController>>,
     mut timer: Local<HealZoneTimer>,
-    mut writer: EventWriter<UpdateHealth>,
+    mut writer: EventWriter<HealEvent>,
 ) {
-    if timer.0.tick(time.delta()).just_finished() {
-        for (transform, beacon, _) in beacons
-            .iter()
-            .filter(|(_, _, state)| **state == BeaconState::Online)
-        {
-            for (entity, player_transform) in players.iter() {
-                let beacon_pos = transform.translation;
-                let player_pos = player_transform.translation;
+    if !timer.0.tick(time.delta()).just_finished() {
+        return;
+    }
+
+    let Ok(player) = players.get_single() else {
+        return;
+    };
 
-                if beacon_pos.distance(player_pos) <= beacon.safe_zone_radius {
-                    writer.send(UpdateHealth(entity, beacon.heal_factor));
+    for zone in zones.iter().filter(|zone| zone.occupants.contains(&player)) {
+        let Ok(beacon) = beacons.get(zone.beacon) else {
+            continue;
+        };
+
+        writer.send(HealEvent {
+            target: player,
+            amount: beacon.heal_factor,
+            overheal: false,
+        });
+    }
+}
+
+/// Applies the effects of any online beacon whose safe zone the player is standing in.
+fn apply_beacon_effects(
+    mut commands: Commands,
+    players: Query<(Entity, Option<&DamageBuff>), With<CharacterController>>,
+    zones: Query<&SafeZone>,
+    beacons: Query<&Beacon>,
+    children: Query<&Children>,
+    mut weapons: Query<&mut Ammo>,
+) {
+    let Ok((player, current_buff)) = players.get_single() else {
+        return;
+    };
+
+    let mut buff: Option<f32> = None;
+
+    for beacon in zones
+        .iter()
+        .filter(|zone| zone.occupants.contains(&player))
+        .filter_map(|zone| beacons.get(zone.beacon).ok())
+    {
+        for effect in beacon.effects.iter() {
+            match effect {
+                BeaconEffect::AmmoRefill => {
+                    for descendant in children.iter_descendants(player) {
+                        if let Ok(mut ammo) = weapons.get_mut(descendant) {
+                            ammo.refill();
+                        }
+                    }
                 }
+                // overlapping buffs don't stack, the strongest one wins.
+                BeaconEffect::DamageBuff { multiplier } => {
+                    buff = Some(buff.map_or(*multiplier, |b| b.max(*multiplier)));
+                }
+                BeaconEffect::Turret { .. } => {}
+            }
+        }
+    }
+
+    match (buff, current_buff) {
+        (Some(multiplier), Some(DamageBuff(current))) if *current == multiplier => {}
+        (Some(multiplier), _) => {
+            commands.entity(player).insert(DamageBuff(multiplier));
+        }
+        (None, Some(_)) => {
+            commands.entity(player).remove::<DamageBuff>();
+        }
+        (None, None) => {}
+    }
+}
+
+fn arm_beacon_turrets(mut commands: Commands, beacons: Query<(Entity, &Beacon), Added<Beacon>>) {
+    for (entity, beacon) in beacons.iter() {
+        for effect in beacon.effects.iter() {
+            if let BeaconEffect::Turret {
+                damage,
+                range,
+                firing_rate,
+            } = effect
+            {
+                commands.entity(entity).insert(BeaconTurret {
+                    damage: *damage,
+                    range: *range,
+                    timer: Timer::from_seconds(60.0 / firing_rate, TimerMode::Repeating),
+                });
             }
         }
     }
 }
 
+fn fire_beacon_turrets(
+    time: Res<Time>,
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    mut turrets: Query<(Entity, &Transform, &BeaconState, &mut BeaconTurret)>,
+    enemies: Query<&Transform, (With<Enemy>, Without<BeaconTurret>)>,
+) {
+    for (beacon, transform, _, mut turret) in turrets
+        .iter_mut()
+        .filter(|(_, _, state, _)| **state == BeaconState::Online)
+    {
+        if !turret.timer.tick(time.delta()).just_finished() {
+            continue;
+        }
+
+        let muzzle = transform.translation + Vec3::Y * 2.0;
+
+        let Some(enemy) = enemies
+            .iter()
+            .map(|enemy| enemy.translation)
+            .filter(|enemy| enemy.distance(muzzle) <= turret.range)
+            .min_by(|a, b| a.distance(muzzle).total_cmp(&b.distance(muzzle)))
+        else {
+            continue;
+        };
+
+        let mut bolt_transform = Transform::from_translation(muzzle).looking_at(enemy, Vec3::Y);
+        bolt_transform.rotate_local_x(PI / 2.0);
+
+        spawn_bolt(
+            &mut commands,
+            &mut meshes,
+            &mut materials,
+            beacon,
+            bolt_transform,
+            turret.damage,
+            25.0,
+        );
+    }
+}
+
+/// Starts activating any offline beacon the player steps into.
 fn activate_beacon(
     mut commands: Commands,
-    keyboard: Res<Input<KeyCode>>,
     players: Query<&Transform, With<CharacterController>>,
     mut beacons: Query<
         (Entity, &Transform, &Beacon, &mut BeaconState),
         Without<CharacterController>,
     >,
     server: Res<AssetServer>,
+    mut activated: EventWriter<BeaconActivated>,
 ) {
-    let player = players.single();
+    let Ok(player) = players.get_single() else {
+        return;
+    };
 
-    if keyboard.pressed(KeyCode::Space) {
-        for (entity, transform, _, mut state) in beacons
-            .iter_mut()
-            .filter(|(_, _, _, state)| **state == BeaconState::Offline)
-        {
-            if player.translation.distance(transform.translation) <= 8.0 {
-                *state = BeaconState::Activated;
-
-                commands.entity(entity).insert(ActivationTimer::default());
-
-                commands.entity(entity).with_children(|parent| {
-                    parent.spawn((
-                        BillboardTextBundle {
-                            transform: Transform::from_xyz(0., 3.0, 0.)
-                                .with_scale(Vec3::splat(0.0085)),
-                            text: Text::from_section(
-                                "15",
-                                TextStyle {
-                                    font_size: 40.,
-                                    color: Color::BLACK,
-                                    font: server.load("fonts/motion-control.bold.otf"),
-                                },
-                            )
-                            .with_alignment(TextAlignment::Center),
-                            ..default()
-                        },
-                        ActivationCountdown,
-                    ));
-                });
+    for (entity, transform, beacon, mut state) in beacons
+        .iter_mut()
+        .filter(|(_, _, _, state)| **state == BeaconState::Offline)
+    {
+        if player.translation.distance(transform.translation) <= beacon.activation_radius {
+            *state = BeaconState::Activated;
+
+            let timer = ActivationTimer::from_seconds(beacon.activation_time);
+            let text = countdown_text(timer.0.duration());
+
+            commands.entity(entity).insert(timer);
+
+            commands.entity(entity).with_children(|parent| {
+                parent.spawn((
+                    BillboardTextBundle {
+                        transform: Transform::from_xyz(0., 3.0, 0.)
+                            .with_scale(Vec3::splat(0.0085)),
+                        text: Text::from_section(
+                            text,
+                            TextStyle {
+                                font_size: 40.,
+                                color: Color::BLACK,
+                                font: server.load("fonts/motion-control.bold.otf"),
+                            },
+                        )
+                        .with_alignment(TextAlignment::Center),
+                        ..default()
+                    },
+                    ActivationCountdown,
+                ));
+            });
+
+            activated.send(BeaconActivated(entity));
+
+            info!("Beacon activated!");
+        }
+    }
+}
 
-                info!("Beacon activated!");
+/// Knocks a beacon back offline when enemies destroy it, so it has to be activated all over again.
+/// Online beacons lose their safe zone and stop holding their portals closed, see
+/// [`PortalDefinition::reopens`](crate::portal::PortalDefinition::reopens).
+fn interrupt_beacons(
+    mut commands: Commands,
+    mut beacons: Query<(Entity, &mut BeaconState, &mut Health, Option<&Children>), Changed<Health>>,
+    countdown_displays: Query<Entity, With<ActivationCountdown>>,
+    mut interrupted: EventWriter<BeaconInterrupted>,
+) {
+    for (entity, mut state, mut health, kiddos) in beacons.iter_mut() {
+        if *state == BeaconState::Offline || health.amount > 0.0 {
+            continue;
+        }
+
+        commands.entity(entity).remove::<ActivationTimer>();
+
+        for kiddo in kiddos.into_iter().flatten() {
+            if let Ok(e) = countdown_displays.get(*kiddo) {
+                commands.entity(e).despawn_recursive();
             }
         }
+
+        *state = BeaconState::Offline;
+        health.amount = health.max;
+
+        interrupted.send(BeaconInterrupted {
+            beacon: entity,
+            reason: InterruptReason::Destroyed,
+        });
+
+        info!("Beacon interrupted!");
     }
 }
 
@@ -179,7 +639,9 @@ fn highlight_nearby_beacons(
     meshes: Query<Entity, With<Handle<Mesh>>>,
     outlines: Query<&mut OutlineVolume>,
 ) {
-    let player = players.single();
+    let Ok(player) = players.get_single() else {
+        return;
+    };
 
     for (entity, transform, _) in beacons_without_outlines
         .iter()
@@ -238,13 +700,7 @@ fn change_beacon_color(
         for descendant in children.iter_descendants(beacon) {
             if let Ok((_, name)) = mat_query.get(descendant) {
                 if name.as_str().contains("Cube.001") {
-                    let color = match state {
-                        BeaconState::Offline => Color::RED,
-                        BeaconState::Activated => Color::ORANGE,
-                        BeaconState::Online => Color::GREEN,
-                    };
-
-                    let mat = materials.add((color * 7.0).into());
+                    let mat = materials.add((state.color() * 7.0).into());
 
                     commands.entity(descendant).insert(mat);
                 }
@@ -253,29 +709,69 @@ fn change_beacon_color(
     }
 }
 
+/// How fast a beacon's activation progress drains away while the player is outside of it,
+/// relative to how fast it builds up.
+const ACTIVATION_DECAY_RATE: f32 = 0.5;
+
+/// Advances the activation of beacons while the player holds them. Progress pauses while enemies
+/// contest the beacon and drains away while the player is outside of it, knocking the beacon back
+/// offline once it's gone.
 fn update_activation_timers(
     time: Res<Time>,
     mut commands: Commands,
+    players: Query<&Transform, With<CharacterController>>,
+    enemies: Query<&Transform, With<Enemy>>,
     mut beacons: Query<(
         Entity,
+        &Beacon,
         &mut ActivationTimer,
         &mut BeaconState,
         &Transform,
         &Children,
     )>,
     mut countdown_displays: Query<(Entity, &mut Text), With<ActivationCountdown>>,
+    mut online: EventWriter<BeaconOnline>,
+    mut interrupted: EventWriter<BeaconInterrupted>,
 ) {
-    for (entity, mut timer, mut state, _, kiddos) in beacons.iter_mut() {
-        //countdown_displays;
+    let Ok(player) = players.get_single() else {
+        return;
+    };
+
+    for (entity, beacon, mut timer, mut state, transform, kiddos) in beacons.iter_mut() {
+        let in_zone = |position: Vec3| {
+            position.distance(transform.translation) <= beacon.activation_radius
+        };
+
+        let held = in_zone(player.translation);
+        let contested = enemies.iter().any(|enemy| in_zone(enemy.translation));
+
+        let color = if !held {
+            let elapsed = timer
+                .0
+                .elapsed()
+                .saturating_sub(time.delta().mul_f32(ACTIVATION_DECAY_RATE));
+
+            timer.0.set_elapsed(elapsed);
+
+            Color::GRAY
+        } else if contested {
+            Color::RED
+        } else {
+            timer.0.tick(time.delta());
+
+            Color::BLACK
+        };
 
         for kiddo in kiddos.iter() {
             if let Ok((_, mut text)) = countdown_displays.get_mut(*kiddo) {
-                text.sections[0].value =
-                    ((timer.0.duration() - timer.0.elapsed()).as_secs() + 1).to_string();
+                text.sections[0].value = countdown_text(timer.0.remaining());
+                text.sections[0].style.color = color;
             }
         }
 
-        if timer.0.tick(time.delta()).finished() {
+        let lost = !held && timer.0.elapsed().is_zero();
+
+        if timer.0.finished() || lost {
             commands.entity(entity).remove::<ActivationTimer>();
 
             for kiddo in kiddos.iter() {
@@ -283,10 +779,23 @@ fn update_activation_timers(
                     commands.entity(e).despawn_recursive();
                 }
             }
+        }
 
+        if timer.0.finished() {
             *state = BeaconState::Online;
 
+            online.send(BeaconOnline(entity));
+
             info!("Beacon online!");
+        } else if lost {
+            *state = BeaconState::Offline;
+
+            interrupted.send(BeaconInterrupted {
+                beacon: entity,
+                reason: InterruptReason::Abandoned,
+            });
+
+            info!("Beacon activation lost!");
         }
     }
 }
@@ -294,7 +803,14 @@ fn update_activation_timers(
 fn show_beacons(beacons: Query<(&Transform, &Beacon, &BeaconState)>, mut gizmos: Gizmos) {
     for (transform, beacon, state) in beacons.iter() {
         match state {
-            BeaconState::Offline => {}
+            BeaconState::Offline => {
+                gizmos.circle(
+                    transform.translation,
+                    Vec3::Y,
+                    beacon.activation_radius,
+                    Color::GRAY,
+                );
+            }
             BeaconState::Activated => {
                 gizmos.circle(
                     transform.translation,
@@ -315,6 +831,19 @@ fn show_beacons(beacons: Query<(&Transform, &Beacon, &BeaconState)>, mut gizmos:
     }
 }
 
+fn play_beacon_sounds(
+    mut commands: Commands,
+    mut online: EventReader<BeaconOnline>,
+    server: Res<AssetServer>,
+) {
+    for _ in online.read() {
+        commands.spawn(AudioBundle {
+            source: server.load("audio/epic-orchestra-transition.wav"),
+            settings: PlaybackSettings::DESPAWN,
+        });
+    }
+}
+
 #[derive(Component)]
 pub struct BeaconCounter;
 
@@ -391,3 +920,58 @@ pub fn update_beacon_ui(
 pub fn all_beacons_online(beacons: Query<&BeaconState>) -> bool {
     beacons.iter().all(|state| *state == BeaconState::Online)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn online_beacon_is_knocked_offline_when_destroyed() {
+        let mut app = App::new();
+
+        app.add_event::<BeaconInterrupted>()
+            .add_systems(Update, interrupt_beacons);
+
+        let beacon = app
+            .world
+            .spawn((Beacon::default(), BeaconState::Online, Health::new(150.0)))
+            .id();
+
+        app.update();
+
+        // it's still standing, so nothing happens.
+        assert_eq!(
+            *app.world.get::<BeaconState>(beacon).unwrap(),
+            BeaconState::Online
+        );
+        assert!(app.world.resource::<Events<BeaconInterrupted>>().is_empty());
+
+        app.world.get_mut::<Health>(beacon).unwrap().amount = 0.0;
+        app.update();
+
+        assert_eq!(
+            *app.world.get::<BeaconState>(beacon).unwrap(),
+            BeaconState::Offline
+        );
+        assert_eq!(app.world.get::<Health>(beacon).unwrap().amount, 150.0);
+        assert_eq!(app.world.resource::<Events<BeaconInterrupted>>().len(), 1);
+    }
+
+    #[test]
+    fn offline_beacon_is_not_interrupted_again() {
+        let mut app = App::new();
+
+        app.add_event::<BeaconInterrupted>()
+            .add_systems(Update, interrupt_beacons);
+
+        let mut health = Health::new(150.0);
+        health.amount = 0.0;
+
+        app.world
+            .spawn((Beacon::default(), BeaconState::Offline, health));
+
+        app.update();
+
+        assert!(app.world.resource::<Events<BeaconInterrupted>>().is_empty());
+    }
+}
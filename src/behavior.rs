@@ -1,14 +1,17 @@
-use std::f32::consts::PI;
-
 use bevy::prelude::*;
 use bevy_mod_picking::backends::raycast::bevy_mod_raycast::{prelude::Raycast, primitives::Ray3d};
-use bevy_xpbd_3d::prelude::{Collider, LinearVelocity, RigidBody};
+use bevy_xpbd_3d::prelude::{
+    Collider, LinearVelocity, RigidBody, Sensor, SpatialQuery, SpatialQueryFilter,
+};
 use rand::{seq::SliceRandom, Rng};
 
 use crate::{
+    beacon::{Beacon, BeaconState},
     controller::{CharacterController, DampingFactor},
-    health::{Health, UpdateHealth},
-    projectile::Damage,
+    health::{DamageEvent, DamageKind, Health},
+    perception::{Perception, PerceptionSet},
+    projectile::{Damage, Projectile},
+    steering::{self, MaxAcceleration, Steering, SteeringWeights, Wander},
     Enemy, GameState,
 };
 
@@ -19,10 +22,18 @@ impl Plugin for BehaviorPlugin {
         app.init_resource::<WaypointCache>()
             .add_systems(
                 Update,
-                ((update_enemy_behavior, attack_player).chain(),)
+                ((
+                    choose_beacon_targets,
+                    update_enemy_behavior,
+                    separate_enemies,
+                    attack_targets,
+                )
+                    .chain(),)
+                    .after(PerceptionSet)
                     .run_if(in_state(GameState::Playing)),
             )
-            .add_systems(OnEnter(GameState::GameOver), crate::cleanup::<Enemy>);
+            .add_systems(OnEnter(GameState::GameOver), crate::cleanup::<Enemy>)
+            .add_systems(OnEnter(GameState::MainMenu), crate::cleanup::<Enemy>);
     }
 }
 
@@ -40,6 +51,11 @@ impl Target {
     pub fn prev(&self) -> Option<Vec3> {
         self.1
     }
+
+    /// The entity the enemy is going after, other than the player.
+    pub fn entity(&self) -> Option<Entity> {
+        self.2
+    }
 }
 
 /// The maximum distance an enemy can be from a target and be consider next to it.
@@ -52,6 +68,15 @@ pub struct VisibiltyRange(pub f32);
 #[derive(Component)]
 pub struct AttackCooldownTimer(pub Timer);
 
+/// Keeps an enemy from crowding its neighbours, so swarms spread out instead of stacking up.
+#[derive(Component)]
+pub struct Separation {
+    /// How close another enemy can get before they start pushing each other apart.
+    pub radius: f32,
+    /// How hard two overlapping enemies push each other apart.
+    pub strength: f32,
+}
+
 #[derive(Bundle)]
 pub struct BehaviorBundle {
     pub target: Target,
@@ -60,6 +85,11 @@ pub struct BehaviorBundle {
     pub damping_factor: DampingFactor,
     pub attack_timer: AttackCooldownTimer,
     pub damage: Damage,
+    pub separation: Separation,
+    pub steering_weights: SteeringWeights,
+    pub max_acceleration: MaxAcceleration,
+    pub wander: Wander,
+    pub perception: Perception,
 }
 
 impl Default for BehaviorBundle {
@@ -71,6 +101,14 @@ impl Default for BehaviorBundle {
             damping_factor: DampingFactor(0.92),
             attack_timer: AttackCooldownTimer(Timer::from_seconds(0.5, TimerMode::Repeating)),
             damage: Damage(1.0),
+            separation: Separation {
+                radius: 2.5,
+                strength: 60.0,
+            },
+            steering_weights: SteeringWeights::default(),
+            max_acceleration: MaxAcceleration(40.0),
+            wander: Wander::default(),
+            perception: Perception::default(),
         }
     }
 }
@@ -94,51 +132,84 @@ fn update_enemy_behavior(
             &ProximityThreshold,
             &VisibiltyRange,
             &mut Target,
+            &SteeringWeights,
+            &MaxAcceleration,
+            &mut Wander,
+            &mut Perception,
         ),
         With<Enemy>,
     >,
-    player: Query<(Entity, &Transform), With<CharacterController>>,
+    player: Query<(Entity, &Transform, &LinearVelocity), (With<CharacterController>, Without<Enemy>)>,
+    beacons: Query<&Transform, (With<Beacon>, Without<Enemy>)>,
+    ignored: Query<Entity, Or<(With<Enemy>, With<Projectile>, With<Sensor>, With<CharacterController>)>>,
+    spatial_query: SpatialQuery,
     mut raycast: Raycast,
     mut gizmos: Gizmos,
 ) {
-    let (entity, player_transform) = player.single();
+    let Ok((_, player_transform, player_velocity)) = player.get_single() else {
+        return;
+    };
 
     let mut rng = rand::thread_rng();
 
-    for (transform, mut lin_vel, threshold, range, mut target) in enemies.iter_mut() {
+    let filter = SpatialQueryFilter::new().without_entities(ignored.iter());
+
+    for (
+        transform,
+        mut lin_vel,
+        threshold,
+        range,
+        mut target,
+        weights,
+        max_acceleration,
+        mut wander,
+        mut perception,
+    ) in enemies.iter_mut()
+    {
         let enemy_pos = transform.translation;
         let player_pos = player_transform.translation;
 
         let dist_to_player = player_pos.distance(enemy_pos);
 
-        //let mut dist;
+        let mut steer = Steering::default();
 
-        if dist_to_player <= 10.0 {
+        if perception.sees_player {
             if dist_to_player >= 6.0 {
-                // move towards player.
-                let d = player_pos - enemy_pos;
-                let angle = d.z.atan2(d.x);
-
-                lin_vel.x += angle.cos() * 40.0 * time.delta_seconds();
-                lin_vel.z += angle.sin() * 40.0 * time.delta_seconds();
+                steer.add(
+                    steering::pursue(
+                        enemy_pos,
+                        player_pos,
+                        player_velocity.0,
+                        lin_vel.length(),
+                        1.0,
+                    ),
+                    weights.pursue,
+                );
             } else {
-                // move away from player.
-                let d = player_pos - enemy_pos;
-                let angle = d.z.atan2(d.x) + PI;
-
-                lin_vel.x += angle.cos() * 40.0 * time.delta_seconds();
-                lin_vel.z += angle.sin() * 40.0 * time.delta_seconds();
+                steer.add(steering::flee(enemy_pos, player_pos), weights.flee);
+            }
+        } else if let Some(beacon) = target.entity().and_then(|entity| beacons.get(entity).ok()) {
+            steer.add(
+                steering::arrive(enemy_pos, beacon.translation, threshold.0),
+                weights.arrive,
+            );
+        } else if let Some(last_known_position) = perception.last_known_position {
+            // go and check where the player was last seen or heard.
+            if last_known_position.distance(enemy_pos) > threshold.0 {
+                steer.add(steering::seek(enemy_pos, last_known_position), weights.seek);
+            } else {
+                perception.forget();
             }
         } else if let Some(waypoint) = target.0 {
             let dist_to_waypoint = waypoint.distance(enemy_pos);
 
             if dist_to_waypoint > threshold.0 {
-                // move towards waypoint.
-                let d = target.0.unwrap() - enemy_pos;
-                let angle = d.z.atan2(d.x);
-
-                lin_vel.x += angle.cos() * 40.0 * time.delta_seconds();
-                lin_vel.z += angle.sin() * 40.0 * time.delta_seconds();
+                steer
+                    .add(steering::arrive(enemy_pos, waypoint, threshold.0 * 2.0), weights.arrive)
+                    .add(
+                        steering::wander(&mut wander, time.delta_seconds(), &mut rng),
+                        weights.wander,
+                    );
             } else {
                 target.1 = target.0;
                 target.0 = None;
@@ -163,6 +234,19 @@ fn update_enemy_behavior(
             );
         }
 
+        steer.add(
+            steering::avoid_obstacles(
+                &spatial_query,
+                enemy_pos,
+                lin_vel.0,
+                3.0 + lin_vel.length() * 0.5,
+                filter.clone(),
+            ),
+            weights.avoid,
+        );
+
+        lin_vel.0 += steer.acceleration(max_acceleration.0) * time.delta_seconds();
+
         /*if target.2.is_none() {
             let distance = player_transform.translation.distance(enemy_pos);
 
@@ -226,22 +310,103 @@ fn update_enemy_behavior(
     }
 }
 
-fn attack_player(
+fn separate_enemies(
+    time: Res<Time>,
+    mut enemies: Query<(Entity, &Transform, &Separation, &mut LinearVelocity), With<Enemy>>,
+) {
+    let positions = enemies
+        .iter()
+        .map(|(entity, transform, _, _)| (entity, transform.translation))
+        .collect::<Vec<_>>();
+
+    for (entity, transform, separation, mut lin_vel) in enemies.iter_mut() {
+        let push = steering::separation(
+            entity,
+            transform.translation,
+            positions.iter().copied(),
+            separation.radius,
+        );
+
+        lin_vel.x += push.x * separation.strength * time.delta_seconds();
+        lin_vel.z += push.z * separation.strength * time.delta_seconds();
+    }
+}
+
+/// Sends enemies that can't see the player after any beacon nearby that's being activated or is
+/// online, preferring ones being activated, and calls them off once the beacon goes offline.
+fn choose_beacon_targets(
+    mut enemies: Query<(&Transform, &VisibiltyRange, &Perception, &mut Target), With<Enemy>>,
+    beacons: Query<(Entity, &Transform, &BeaconState), (With<Beacon>, Without<Enemy>)>,
+) {
+    for (transform, range, perception, mut target) in enemies.iter_mut() {
+        let still_up = target
+            .entity()
+            .and_then(|entity| beacons.get(entity).ok())
+            .is_some_and(|(_, _, state)| *state != BeaconState::Offline);
+
+        if !still_up {
+            target.2 = None;
+        }
+
+        if target.2.is_some() || perception.sees_player {
+            continue;
+        }
+
+        target.2 = beacons
+            .iter()
+            .filter(|(_, _, state)| **state != BeaconState::Offline)
+            .map(|(entity, beacon_transform, state)| {
+                (
+                    entity,
+                    *state == BeaconState::Online,
+                    beacon_transform.translation.distance(transform.translation),
+                )
+            })
+            .filter(|(_, _, distance)| *distance <= range.0)
+            // beacons being activated come first, since `false` sorts before `true`.
+            .min_by(|(_, a_online, a), (_, b_online, b)| {
+                a_online.cmp(b_online).then(a.total_cmp(b))
+            })
+            .map(|(entity, _, _)| entity);
+    }
+}
+
+/// The maximum distance an enemy can attack from.
+const ATTACK_RANGE: f32 = 7.0;
+
+fn attack_targets(
     time: Res<Time>,
     player: Query<(Entity, &Transform), (With<CharacterController>, Without<Enemy>)>,
-    mut enemies: Query<(&Transform, &Damage, &mut AttackCooldownTimer), With<Enemy>>,
-    mut event_writer: EventWriter<UpdateHealth>,
+    beacons: Query<&Transform, (With<Beacon>, Without<Enemy>)>,
+    mut enemies: Query<
+        (Entity, &Transform, &Damage, &Target, &mut AttackCooldownTimer),
+        With<Enemy>,
+    >,
+    mut event_writer: EventWriter<DamageEvent>,
 ) {
-    let (player, player_transform) = player.single();
+    let Ok((player, player_transform)) = player.get_single() else {
+        return;
+    };
 
-    for (enemy_transform, damage, mut timer) in enemies.iter_mut() {
-        if player_transform
-            .translation
-            .distance(enemy_transform.translation)
-            <= 7.0
-        {
+    for (enemy, enemy_transform, damage, target, mut timer) in enemies.iter_mut() {
+        let enemy_pos = enemy_transform.translation;
+
+        // the player always takes priority over beacons.
+        let victim = if player_transform.translation.distance(enemy_pos) <= ATTACK_RANGE {
+            Some(player)
+        } else {
+            target.entity().filter(|entity| {
+                beacons
+                    .get(*entity)
+                    .is_ok_and(|beacon| beacon.translation.distance(enemy_pos) <= ATTACK_RANGE)
+            })
+        };
+
+        if let Some(victim) = victim {
             if timer.0.tick(time.delta()).just_finished() {
-                event_writer.send(UpdateHealth(player, -damage.0));
+                event_writer.send(
+                    DamageEvent::new(victim, damage.0, DamageKind::Melee).with_source(enemy),
+                );
             }
         }
     }
@@ -56,7 +56,10 @@ fn follow_player(
         return;
     };
 
-    let mut camera_transform = camera.single_mut();
+    let Ok(mut camera_transform) = camera.get_single_mut() else {
+        return;
+    };
+
     *camera_transform = *controller_transform;
     camera_transform.translation.y += 1.0;
     //camera_transform.translation.z -= 1.0;
@@ -0,0 +1,175 @@
+use bevy::prelude::*;
+use bevy_xpbd_3d::prelude::LinearVelocity;
+
+use crate::{
+    beacon::BeaconOnline,
+    controller::CharacterController,
+    game_over::Outcome,
+    health::{Died, Health, InvulnerabilityWindow},
+    objectives::Objective,
+    perception::Perception,
+    triggers::CheckpointReached,
+    Enemy, GameState, InGame,
+};
+
+/// Brings the player back at their last checkpoint when they die, for as long as they have lives
+/// left.
+pub struct CheckpointPlugin;
+
+impl Plugin for CheckpointPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            OnEnter(GameState::BeginGame),
+            (start_at_spawn_point, spawn_lives_ui),
+        )
+        .add_systems(
+            Update,
+            (
+                reach_checkpoints,
+                beacon_checkpoints.run_if(on_event::<BeaconOnline>()),
+                respawn_player.run_if(on_event::<Died>()),
+                update_lives_ui,
+            )
+                .chain()
+                .run_if(in_state(GameState::Playing)),
+        );
+    }
+}
+
+/// Where the player comes back after dying.
+#[derive(Resource, Debug)]
+pub struct Checkpoint(pub Vec3);
+
+/// How many more times the player can die before losing the level.
+#[derive(Resource, Debug)]
+pub struct Lives(pub u32);
+
+fn start_at_spawn_point(
+    mut commands: Commands,
+    objective: Res<Objective>,
+    player: Query<&Transform, With<CharacterController>>,
+) {
+    commands.insert_resource(Lives(objective.lives));
+
+    let Ok(transform) = player.get_single() else {
+        return;
+    };
+
+    commands.insert_resource(Checkpoint(transform.translation));
+}
+
+fn reach_checkpoints(mut commands: Commands, mut reached: EventReader<CheckpointReached>) {
+    for CheckpointReached(position) in reached.read() {
+        info!("Checkpoint reached at {position}");
+
+        commands.insert_resource(Checkpoint(*position));
+    }
+}
+
+/// Beacons double as checkpoints once they're online.
+fn beacon_checkpoints(
+    mut commands: Commands,
+    mut online: EventReader<BeaconOnline>,
+    beacons: Query<&Transform>,
+) {
+    for BeaconOnline(beacon) in online.read() {
+        let Ok(transform) = beacons.get(*beacon) else {
+            continue;
+        };
+
+        // beacons sit a little lower than the player does.
+        commands.insert_resource(Checkpoint(transform.translation + Vec3::Y));
+    }
+}
+
+/// Moves a dead player back to their checkpoint with full health. The rest of the level is left as
+/// it is, so beacons keep their progress. Once the player is out of lives, the level is lost.
+fn respawn_player(
+    mut commands: Commands,
+    mut next_state: ResMut<NextState<GameState>>,
+    checkpoint: Option<Res<Checkpoint>>,
+    mut lives: ResMut<Lives>,
+    mut died: EventReader<Died>,
+    mut player: Query<
+        (
+            Entity,
+            &mut Health,
+            &mut Transform,
+            &mut LinearVelocity,
+            Option<&mut InvulnerabilityWindow>,
+        ),
+        With<CharacterController>,
+    >,
+    mut enemies: Query<&mut Perception, With<Enemy>>,
+) {
+    let Ok((entity, mut health, mut transform, mut velocity, invulnerability)) =
+        player.get_single_mut()
+    else {
+        return;
+    };
+
+    // read every death, so none of them are left over for the next frame.
+    if died.read().filter(|died| died.entity == entity).count() == 0 {
+        return;
+    }
+
+    if lives.0 == 0 {
+        info!("Out of lives, game over");
+
+        next_state.set(GameState::GameOver);
+        commands.insert_resource(Outcome::Lost);
+
+        return;
+    }
+
+    lives.0 -= 1;
+
+    if let Some(checkpoint) = checkpoint {
+        transform.translation = checkpoint.0;
+    }
+
+    velocity.0 = Vec3::ZERO;
+    health.amount = health.max;
+
+    if let Some(mut window) = invulnerability {
+        window.0.reset();
+    }
+
+    // the player is somewhere else now, so enemies have to find them all over again.
+    for mut perception in enemies.iter_mut() {
+        perception.sees_player = false;
+        perception.forget();
+    }
+
+    info!("Respawned, {} lives left", lives.0);
+}
+
+#[derive(Component)]
+pub struct LivesDisplay;
+
+fn spawn_lives_ui(mut commands: Commands, server: Res<AssetServer>) {
+    commands.spawn((
+        TextBundle::from_section(
+            "",
+            TextStyle {
+                font_size: 20.,
+                color: Color::rgb_u8(56, 193, 235),
+                font: server.load("fonts/motion-control.bold.otf"),
+            },
+        )
+        .with_style(Style {
+            position_type: PositionType::Absolute,
+            bottom: Val::Px(35.0),
+            left: Val::Px(10.0),
+            ..default()
+        }),
+        LivesDisplay,
+        InGame,
+    ));
+}
+
+fn update_lives_ui(lives: Res<Lives>, mut displays: Query<&mut Text, With<LivesDisplay>>) {
+    for mut text in displays.iter_mut() {
+        text.sections[0].value = format!("LIVES {}", lives.0);
+    }
+}
@@ -0,0 +1,161 @@
+use std::f32::consts::PI;
+
+use bevy::prelude::*;
+
+use crate::{
+    beacon::{Beacon, BeaconState},
+    controller::CharacterController,
+    portal::{Portal, PortalState},
+    GameState, InGame,
+};
+
+pub struct CompassPlugin;
+
+impl Plugin for CompassPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(OnEnter(GameState::BeginGame), spawn_compass)
+            .add_systems(
+                Update,
+                (spawn_compass_markers, update_compass_markers)
+                    .chain()
+                    .run_if(in_state(GameState::Playing)),
+            );
+    }
+}
+
+/// How far either side of where the player is facing the compass shows, in radians.
+const COMPASS_HALF_ANGLE: f32 = PI / 2.0;
+
+#[derive(Component)]
+pub struct Compass;
+
+/// A mark on the compass showing which way a beacon or portal is from the player.
+#[derive(Component)]
+pub struct CompassMarker(pub Entity);
+
+fn spawn_compass(mut commands: Commands) {
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    width: Val::Percent(100.0),
+                    top: Val::Px(36.0),
+                    justify_content: JustifyContent::Center,
+                    ..default()
+                },
+                ..default()
+            },
+            InGame,
+        ))
+        .with_children(|parent| {
+            parent
+                .spawn((
+                    NodeBundle {
+                        style: Style {
+                            width: Val::Percent(40.0),
+                            height: Val::Px(18.0),
+                            border: UiRect::all(Val::Px(1.0)),
+                            ..default()
+                        },
+                        background_color: Color::DARK_GRAY.with_a(0.6).into(),
+                        border_color: Color::BLACK.into(),
+                        ..default()
+                    },
+                    Compass,
+                ))
+                .with_children(|parent| {
+                    // marks straight ahead.
+                    parent.spawn(NodeBundle {
+                        style: Style {
+                            position_type: PositionType::Absolute,
+                            left: Val::Percent(50.0),
+                            width: Val::Px(2.0),
+                            height: Val::Percent(100.0),
+                            ..default()
+                        },
+                        background_color: Color::WHITE.into(),
+                        ..default()
+                    });
+                });
+        });
+}
+
+fn spawn_compass_markers(
+    mut commands: Commands,
+    compasses: Query<Entity, With<Compass>>,
+    beacons: Query<Entity, Added<Beacon>>,
+    portals: Query<Entity, Added<Portal>>,
+) {
+    let Ok(compass) = compasses.get_single() else {
+        return;
+    };
+
+    let markers = beacons
+        .iter()
+        .map(|entity| (entity, 10.0))
+        .chain(portals.iter().map(|entity| (entity, 14.0)));
+
+    for (entity, size) in markers {
+        commands
+            .spawn((
+                NodeBundle {
+                    style: Style {
+                        position_type: PositionType::Absolute,
+                        width: Val::Px(size),
+                        height: Val::Px(size),
+                        top: Val::Px((16.0 - size) / 2.0),
+                        margin: UiRect::left(Val::Px(-size / 2.0)),
+                        border: UiRect::all(Val::Px(1.0)),
+                        ..default()
+                    },
+                    border_color: Color::BLACK.into(),
+                    ..default()
+                },
+                CompassMarker(entity),
+            ))
+            .set_parent(compass);
+    }
+}
+
+/// Slides each marker along the compass to match where its beacon or portal is relative to the
+/// player. Anything outside of the compass sticks to its edges, so it can always be found.
+fn update_compass_markers(
+    mut commands: Commands,
+    players: Query<&Transform, With<CharacterController>>,
+    targets: Query<(&Transform, Option<&BeaconState>, Option<&PortalState>)>,
+    mut markers: Query<(Entity, &CompassMarker, &mut Style, &mut BackgroundColor)>,
+) {
+    let Ok(player) = players.get_single() else {
+        return;
+    };
+
+    let forward = Vec3::new(player.forward().x, 0.0, player.forward().z).normalize_or_zero();
+    let right = Vec3::new(-forward.z, 0.0, forward.x);
+
+    for (marker, CompassMarker(target), mut style, mut color) in markers.iter_mut() {
+        let Ok((transform, beacon_state, portal_state)) = targets.get(*target) else {
+            commands.entity(marker).despawn_recursive();
+            continue;
+        };
+
+        let offset = transform.translation - player.translation;
+        let bearing = offset.dot(right).atan2(offset.dot(forward));
+        let in_view = bearing.abs() <= COMPASS_HALF_ANGLE;
+
+        let position = bearing.clamp(-COMPASS_HALF_ANGLE, COMPASS_HALF_ANGLE) / COMPASS_HALF_ANGLE;
+        style.left = Val::Percent(50.0 + position * 50.0);
+
+        let state_color = beacon_state
+            .map(|state| state.color())
+            .or(portal_state.map(|state| state.color()))
+            .unwrap_or(Color::WHITE);
+
+        *color = if in_view {
+            state_color
+        } else {
+            state_color.with_a(0.4)
+        }
+        .into();
+    }
+}
@@ -1,10 +1,14 @@
 use bevy::{input::mouse::MouseWheel, prelude::*};
 use bevy_xpbd_3d::{
-    prelude::{ColliderParent, Collisions, LinearVelocity, Position, RigidBody, Rotation, Sensor, AngularVelocity},
+    prelude::{
+        AngularVelocity, Collider, ColliderParent, Collisions, DeltaTime, LinearVelocity,
+        PhysicsSchedule, PhysicsStepSet, Position, RigidBody, Rotation, Sensor, SpatialQuery,
+        SpatialQueryFilter,
+    },
     SubstepSchedule, SubstepSet,
 };
 
-use crate::{GameState, projectile::Projectile, Enemy};
+use crate::{GameState, projectile::Projectile, Enemy, Layer};
 
 pub struct CharacterControllerPlugin;
 
@@ -19,6 +23,11 @@ impl Plugin for CharacterControllerPlugin {
                 .run_if(in_state(GameState::Playing)),
         );
 
+        app.add_systems(
+            PhysicsSchedule,
+            collide_and_slide.before(PhysicsStepSet::BroadPhase),
+        );
+
         app.add_systems(
             SubstepSchedule,
             handle_collisions.in_set(SubstepSet::SolveUserConstraints),
@@ -48,6 +57,21 @@ pub struct AngularDampingFactor(pub f32);
 #[component(storage = "SparseSet")]
 pub struct IsGrounded;
 
+/// The maximum number of times a character's motion can be redirected along a surface in a single
+/// physics step.
+const MAX_SLIDES: usize = 4;
+
+/// The maximum number of floors and ceilings a character's motion can follow in a single physics
+/// step. These don't count towards [`MAX_SLIDES`], so sloped floors don't use up the slides walls
+/// need.
+const MAX_FLOOR_CONTACTS: usize = 4;
+
+/// The gap kept between a character and any surface it is sliding along.
+const SKIN_WIDTH: f32 = 0.05;
+
+/// Surfaces whose normal points further up or down than this are treated as floors or ceilings.
+const MAX_WALL_NORMAL_Y: f32 = 0.7;
+
 #[derive(Bundle)]
 pub struct CharacterControllerBundle {
     pub character_controller: CharacterController,
@@ -123,7 +147,9 @@ fn handle_character_actions(
         With<CharacterController>,
     >,
 ) {
-    let (mut transform, walking_speed, rotation_speed, pitch_speed, mut lin_velocity, mut angular_vel) = controllers.single_mut();
+    let Ok((mut transform, walking_speed, rotation_speed, pitch_speed, mut lin_velocity, mut angular_vel)) = controllers.get_single_mut() else {
+        return;
+    };
 
     for action in reader.read() {
         match action {
@@ -180,12 +206,157 @@ fn apply_angular_damping(mut query: Query<(&AngularDampingFactor, &mut AngularVe
     }
 }
 
+/// Sweeps the collider of each character and enemy along its velocity and redirects the motion
+/// along any surface it would hit, so nothing can tunnel through geometry or get wedged in corners.
+///
+/// Characters and enemies are ignored by the sweep, since enemies keep their distance through
+/// [`Separation`](crate::behavior::Separation) instead. Floors and ceilings grazed along the way
+/// redirect the motion along them rather than stopping it, so the sweep carries on towards any wall
+/// past them. Whatever motion is left once the slides run out moves the character as far as the
+/// next contact.
+///
+/// The resulting velocity is written back to [`LinearVelocity`], which the physics integrator
+/// then uses to move the character.
+fn collide_and_slide(
+    delta_time: Res<DeltaTime>,
+    spatial_query: SpatialQuery,
+    ignored: Query<
+        Entity,
+        Or<(
+            With<Sensor>,
+            With<Projectile>,
+            With<Enemy>,
+            With<CharacterController>,
+        )>,
+    >,
+    mut controllers: Query<
+        (&Collider, &Position, &mut LinearVelocity),
+        Or<(With<CharacterController>, With<Enemy>)>,
+    >,
+) {
+    let dt = delta_time.0;
+
+    if dt <= 0.0 {
+        return;
+    }
+
+    // every controller is in the ignored set, so one filter does for all of them. Corpses are on a
+    // layer of their own, so they never get in the way.
+    let filter = SpatialQueryFilter::new()
+        .with_masks([Layer::Level, Layer::Character])
+        .without_entities(ignored.iter());
+
+    for (collider, position, mut lin_vel) in controllers.iter_mut() {
+        let mut origin = position.0;
+        let mut motion = Vec3::new(lin_vel.x, 0.0, lin_vel.z) * dt;
+        let mut first_normal: Option<Vec3> = None;
+        let mut slides = 0;
+        let mut floor_contacts = 0;
+
+        while slides < MAX_SLIDES && floor_contacts < MAX_FLOOR_CONTACTS {
+            let distance = motion.length();
+
+            if distance <= f32::EPSILON {
+                break;
+            }
+
+            let direction = motion / distance;
+
+            // The collider is swept upright, so the pitch of the character never tips it into the floor.
+            let Some(hit) = spatial_query.cast_shape(
+                collider,
+                origin,
+                Quat::IDENTITY,
+                direction,
+                distance + SKIN_WIDTH,
+                true,
+                filter.clone(),
+            ) else {
+                origin += motion;
+                motion = Vec3::ZERO;
+                break;
+            };
+
+            let travel = (hit.time_of_impact - SKIN_WIDTH).max(0.0);
+            origin += direction * travel;
+
+            let remaining = motion - direction * travel;
+
+            // Characters only move on the horizontal plane, so floors and ceilings they graze
+            // don't block them. The rest of the motion follows the surface and is swept again from
+            // just off of it, so walls further along still do.
+            if hit.normal1.y.abs() > MAX_WALL_NORMAL_Y {
+                // the outward normal of the surface, which points back at the character.
+                let normal = hit.normal1.normalize_or_zero();
+
+                origin += normal * SKIN_WIDTH;
+                motion = remaining - normal * remaining.dot(normal);
+                floor_contacts += 1;
+                continue;
+            }
+
+            slides += 1;
+
+            // Only the horizontal part of a wall is slid along, and the normal always opposes the
+            // motion that hit it.
+            let mut normal = Vec3::new(hit.normal1.x, 0.0, hit.normal1.z).normalize_or_zero();
+            if normal.dot(direction) > 0.0 {
+                normal = -normal;
+            }
+
+            motion = match first_normal {
+                // Wedged between two surfaces, so only move along the crease they form.
+                Some(first) if first.dot(normal) < 0.99 => {
+                    let crease = first.cross(normal).normalize_or_zero();
+                    crease * remaining.dot(crease)
+                }
+                _ => remaining - normal * remaining.dot(normal),
+            };
+
+            first_normal.get_or_insert(normal);
+        }
+
+        // Out of slides, so whatever motion is left only goes as far as the next contact.
+        let distance = motion.length();
+
+        if distance > f32::EPSILON {
+            let direction = motion / distance;
+
+            let travel = spatial_query
+                .cast_shape(
+                    collider,
+                    origin,
+                    Quat::IDENTITY,
+                    direction,
+                    distance + SKIN_WIDTH,
+                    true,
+                    filter.clone(),
+                )
+                .map_or(distance, |hit| {
+                    (hit.time_of_impact - SKIN_WIDTH).clamp(0.0, distance)
+                });
+
+            origin += direction * travel;
+        }
+
+        let slid = (origin - position.0) / dt;
+
+        lin_vel.x = slid.x;
+        lin_vel.z = slid.z;
+    }
+}
+
+/// Pushes characters and enemies out of any level geometry they still overlap, e.g. geometry that
+/// moved into them.
+///
+/// Only the deepest contact of each manifold is resolved, since contacts in a manifold share a
+/// normal and resolving all of them would push the body out several times over.
 fn handle_collisions(
     collisions: Res<Collisions>,
-    collider_parents: Query<&ColliderParent, (Without<Sensor>, Without<Projectile>, Without<Enemy>)>,
+    collider_parents: Query<&ColliderParent, (Without<Sensor>, Without<Projectile>)>,
     mut controllers: Query<
-        (&RigidBody, &mut Position, &Rotation, &mut LinearVelocity),
-        With<CharacterController>,
+        (&RigidBody, &mut Position, &Rotation),
+        Or<(With<CharacterController>, With<Enemy>)>,
     >,
 ) {
     for contacts in collisions.iter() {
@@ -199,8 +370,13 @@ fn handle_collisions(
             continue;
         };
 
+        // Characters and enemies don't push each other around.
+        if controllers.contains(collider_parent_1.get()) && controllers.contains(collider_parent_2.get()) {
+            continue;
+        }
+
         let is_first: bool;
-        let (rb, mut pos, rot, mut lin_vel) = if let Ok(contoller) = controllers.get_mut(collider_parent_1.get()) {
+        let (rb, mut pos, rot) = if let Ok(contoller) = controllers.get_mut(collider_parent_1.get()) {
             is_first = true;
             contoller
         } else if let Ok(contoller) = controllers.get_mut(collider_parent_2.get()) {
@@ -221,8 +397,14 @@ fn handle_collisions(
                 -manifold.global_normal2(rot)
             };
 
-            for contact in manifold.contacts.iter().filter(|c| c.penetration > 0.0) {
-                pos.0 += normal * contact.penetration;
+            let penetration = manifold
+                .contacts
+                .iter()
+                .map(|contact| contact.penetration)
+                .fold(0.0, f32::max);
+
+            if penetration > 0.0 {
+                pos.0 += normal * penetration;
             }
         }
     }
@@ -0,0 +1,215 @@
+use std::f32::consts::TAU;
+
+use bevy::prelude::*;
+use bevy_xpbd_3d::prelude::{AngularVelocity, CollisionLayers, LinearVelocity, RigidBody};
+use rand::Rng;
+use serde::Deserialize;
+
+use crate::{
+    health::{DamageEvent, DamageKind, Died, Health},
+    Enemy, GameState, InGame, KillCount, Layer,
+};
+
+/// Decides what happens to things once they die.
+pub struct DeathPlugin;
+
+impl Plugin for DeathPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<LootDropped>().add_systems(
+            Update,
+            (count_kills, handle_deaths, update_corpses)
+                .chain()
+                .run_if(in_state(GameState::Playing)),
+        );
+    }
+}
+
+/// What happens to something when it dies. Anything without one is despawned on the spot.
+///
+/// Portals can pick what happens to the creeps they spawn through their extras, e.g.
+/// `{ "creep_death": { "type": "sink", "seconds": 2.0 } }`.
+#[derive(Component, Deserialize, Clone, Copy, Debug)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum DeathBehavior {
+    Despawn,
+    /// Nothing happens here, something else deals with the death. Beacons, portals and the player
+    /// all have their own way of dying, see [`Died`].
+    Remain,
+    /// Goes off, damaging everything within `radius`.
+    Explode {
+        radius: f32,
+        damage: f32,
+    },
+    /// Sinks into the floor over `seconds`.
+    Sink {
+        seconds: f32,
+    },
+    /// Goes limp and tumbles over, then disappears after `seconds`.
+    Ragdoll {
+        seconds: f32,
+    },
+}
+
+/// Gives something a `chance` of dropping loot when it dies, see [`LootDropped`].
+#[derive(Component, Clone, Copy, Debug)]
+pub struct DropsLoot {
+    pub chance: f32,
+}
+
+/// Sent when something with [`DropsLoot`] dies and drops loot where it died.
+#[derive(Event)]
+pub struct LootDropped(pub Vec3);
+
+/// Something on its way out, despawned once its timer runs out.
+#[derive(Component)]
+pub struct Corpse {
+    pub timer: Timer,
+    /// How fast the corpse sinks into the floor, in units per second.
+    pub sink_speed: f32,
+}
+
+/// How deep sinking corpses go before they're gone.
+const SINK_DEPTH: f32 = 3.0;
+
+fn count_kills(
+    mut died: EventReader<Died>,
+    enemies: Query<(), With<Enemy>>,
+    mut kill_count: ResMut<KillCount>,
+) {
+    for Died { entity, .. } in died.read() {
+        if enemies.contains(*entity) {
+            kill_count.0 += 1;
+        }
+    }
+}
+
+fn handle_deaths(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    mut died: EventReader<Died>,
+    dying: Query<(Option<&DeathBehavior>, Option<&DropsLoot>)>,
+    damageable: Query<(Entity, &GlobalTransform), With<Health>>,
+    mut damage: EventWriter<DamageEvent>,
+    mut loot: EventWriter<LootDropped>,
+) {
+    let mut rng = rand::thread_rng();
+
+    for Died {
+        entity, position, ..
+    } in died.read()
+    {
+        let Ok((behavior, drops_loot)) = dying.get(*entity) else {
+            continue;
+        };
+
+        if drops_loot
+            .is_some_and(|DropsLoot { chance }| rng.gen_bool(chance.clamp(0.0, 1.0) as f64))
+        {
+            loot.send(LootDropped(*position));
+        }
+
+        match behavior.copied().unwrap_or(DeathBehavior::Despawn) {
+            DeathBehavior::Despawn => {
+                commands.entity(*entity).despawn_recursive();
+            }
+            DeathBehavior::Remain => {}
+            DeathBehavior::Explode {
+                radius,
+                damage: amount,
+            } => {
+                for (other, transform) in damageable.iter() {
+                    if other != *entity && transform.translation().distance(*position) <= radius {
+                        damage.send(
+                            DamageEvent::new(other, amount, DamageKind::Environmental)
+                                .with_source(*entity)
+                                .with_point(*position),
+                        );
+                    }
+                }
+
+                commands.spawn((
+                    PbrBundle {
+                        mesh: meshes.add(
+                            shape::UVSphere {
+                                radius,
+                                ..default()
+                            }
+                            .into(),
+                        ),
+                        material: materials.add(StandardMaterial {
+                            base_color: Color::ORANGE.with_a(0.5),
+                            emissive: Color::rgb(8.0, 3.0, 0.0),
+                            alpha_mode: AlphaMode::Blend,
+                            unlit: true,
+                            ..default()
+                        }),
+                        transform: Transform::from_translation(*position),
+                        ..default()
+                    },
+                    Corpse {
+                        timer: Timer::from_seconds(0.2, TimerMode::Once),
+                        sink_speed: 0.0,
+                    },
+                    InGame,
+                ));
+
+                commands.entity(*entity).despawn_recursive();
+            }
+            DeathBehavior::Sink { seconds } => {
+                commands.entity(*entity).remove::<Enemy>().insert((
+                    Corpse {
+                        timer: Timer::from_seconds(seconds, TimerMode::Once),
+                        sink_speed: SINK_DEPTH / seconds,
+                    },
+                    RigidBody::Static,
+                    CollisionLayers::new([Layer::Corpse], [Layer::Level]),
+                    InGame,
+                ));
+            }
+            DeathBehavior::Ragdoll { seconds } => {
+                let spin = Vec3::new(rng.gen_range(-1.0..1.0), 0.0, rng.gen_range(-1.0..1.0))
+                    .normalize_or_zero()
+                    * rng.gen_range(0.5..1.0)
+                    * TAU;
+
+                commands.entity(*entity).remove::<Enemy>().insert((
+                    Corpse {
+                        timer: Timer::from_seconds(seconds, TimerMode::Once),
+                        sink_speed: 0.0,
+                    },
+                    RigidBody::Dynamic,
+                    AngularVelocity(spin),
+                    // only the level stops it, so it doesn't get in anyone's way while it falls.
+                    CollisionLayers::new([Layer::Corpse], [Layer::Level]),
+                    InGame,
+                ));
+            }
+        }
+    }
+}
+
+fn update_corpses(
+    time: Res<Time>,
+    mut commands: Commands,
+    mut corpses: Query<(
+        Entity,
+        &mut Corpse,
+        &mut Transform,
+        Option<&mut LinearVelocity>,
+    )>,
+) {
+    for (entity, mut corpse, mut transform, velocity) in corpses.iter_mut() {
+        if corpse.sink_speed > 0.0 {
+            transform.translation.y -= corpse.sink_speed * time.delta_seconds();
+
+            if let Some(mut velocity) = velocity {
+                velocity.0 = Vec3::ZERO;
+            }
+        }
+
+        if corpse.timer.tick(time.delta()).finished() {
+            commands.entity(entity).despawn_recursive();
+        }
+    }
+}
@@ -1,13 +1,29 @@
-use bevy::prelude::*;
+use bevy::{gltf::GltfExtras, prelude::*, utils::HashSet};
 use bevy_xpbd_3d::prelude::Collisions;
+use serde::Deserialize;
 
-use crate::{controller::CharacterController, GameState};
+use crate::{beacon::BeaconState, controller::CharacterController, Enemy, GameState};
 
 pub struct DoorPlugin;
 
 impl Plugin for DoorPlugin {
     fn build(&self, app: &mut App) {
-        app.add_systems(Update, trigger_door.run_if(in_state(GameState::Playing)));
+        app.init_resource::<Keys>()
+            .add_systems(OnEnter(GameState::BeginGame), reset_keys)
+            .add_systems(
+                Update,
+                (
+                    pick_up_keys,
+                    unlock_doors,
+                    trigger_door,
+                    slide_doors,
+                    link_door_visuals,
+                    move_door_visuals,
+                    show_locked_doors,
+                )
+                    .chain()
+                    .run_if(in_state(GameState::Playing)),
+            );
     }
 }
 
@@ -17,19 +33,298 @@ pub struct Door;
 #[derive(Component)]
 pub struct DoorTrigger(pub Entity);
 
+/// A door's definition. Levels can override any of it through the extras of the door's node, e.g.
+/// `{ "slide": [0.0, 4.0, 0.0], "trigger_size": [6.0, 3.0, 3.0], "lock": { "type": "key", "key": "red" } }`.
+#[derive(Component, Deserialize, Clone, Debug)]
+#[serde(default)]
+pub struct DoorDefinition {
+    /// How far the door moves when it opens.
+    pub slide: [f32; 3],
+    /// How long the door takes to open, in seconds.
+    pub open_time: f32,
+    /// The size of the box around the door the player has to walk into to open it.
+    pub trigger_size: [f32; 3],
+    /// What has to happen before the door can be opened, if anything.
+    pub lock: Option<DoorLock>,
+}
+
+impl Default for DoorDefinition {
+    fn default() -> Self {
+        Self {
+            slide: [0.0, -4.0, 0.0],
+            open_time: 1.0,
+            trigger_size: [3.0, 3.0, 3.0],
+            lock: None,
+        }
+    }
+}
+
+/// The shortest time a door can take to open, in seconds, so it's still seen sliding open.
+const MIN_OPEN_TIME: f32 = 0.05;
+
+impl DoorDefinition {
+    /// Reads a door's definition from the extras of its level node, falling back to the defaults
+    /// for anything left out.
+    pub fn from_extras(extras: Option<&GltfExtras>) -> Self {
+        let Some(extras) = extras else {
+            return Self::default();
+        };
+
+        let mut definition: Self = serde_json::from_str(&extras.value).unwrap_or_else(|err| {
+            warn!("invalid door definition {:?}: {err}", extras.value);
+            Self::default()
+        });
+
+        // the open time ends up as a timer duration, which panics on anything negative or NaN.
+        if !(definition.open_time.is_finite() && definition.open_time >= MIN_OPEN_TIME) {
+            warn!("invalid door open time {}, using the default", definition.open_time);
+            definition.open_time = Self::default().open_time;
+        }
+
+        definition
+    }
+
+    /// The state a door with this definition starts out in.
+    pub fn initial_state(&self) -> DoorState {
+        match self.lock {
+            Some(_) => DoorState::Locked,
+            None => DoorState::Closed,
+        }
+    }
+}
+
+/// What keeps a locked door shut.
+#[derive(Deserialize, Clone, Debug)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum DoorLock {
+    /// The player has to pick up the key called `key`.
+    Key { key: String },
+    /// At least `count` beacons have to be online.
+    BeaconsOnline { count: usize },
+    /// Every enemy within `radius` of the door has to be dead.
+    RoomCleared { radius: f32 },
+}
+
+#[derive(Component, PartialEq, Eq, Copy, Clone, Debug, Default)]
+pub enum DoorState {
+    #[default]
+    Closed,
+    Opening,
+    Open,
+    /// The door won't open until its [`DoorLock`] is satisfied.
+    Locked,
+}
+
+/// Where a door sits when closed, and how far along opening it is.
+#[derive(Component)]
+pub struct DoorSlide {
+    pub closed: Vec3,
+    pub timer: Timer,
+}
+
+impl DoorSlide {
+    pub fn new(closed: Vec3, open_time: f32) -> Self {
+        Self {
+            closed,
+            timer: Timer::from_seconds(open_time, TimerMode::Once),
+        }
+    }
+}
+
+/// Part of the level's scene that moves along with a door, so the door is seen opening rather
+/// than just its collider.
+#[derive(Component)]
+pub struct DoorVisual {
+    pub door: Entity,
+    pub closed: Vec3,
+}
+
+/// A key the player can pick up to open doors locked with [`DoorLock::Key`].
+#[derive(Component)]
+pub struct KeyItem(pub String);
+
+impl KeyItem {
+    /// Reads a key's name from the extras of its level node, e.g. `{ "key": "red" }`.
+    pub fn from_extras(extras: Option<&GltfExtras>) -> Option<Self> {
+        #[derive(Deserialize)]
+        struct KeyDefinition {
+            key: String,
+        }
+
+        let extras = extras?;
+
+        match serde_json::from_str::<KeyDefinition>(&extras.value) {
+            Ok(definition) => Some(KeyItem(definition.key)),
+            Err(err) => {
+                warn!("invalid key definition {:?}: {err}", extras.value);
+                None
+            }
+        }
+    }
+}
+
+/// The keys the player has picked up this level.
+#[derive(Resource, Default, Debug)]
+pub struct Keys(pub HashSet<String>);
+
+fn reset_keys(mut keys: ResMut<Keys>) {
+    keys.0.clear();
+}
+
+fn pick_up_keys(
+    mut commands: Commands,
+    collisions: Res<Collisions>,
+    mut keys: ResMut<Keys>,
+    character: Query<Entity, With<CharacterController>>,
+    items: Query<(Entity, &KeyItem)>,
+) {
+    let Ok(character_entity) = character.get_single() else {
+        return;
+    };
+
+    for (entity, KeyItem(key)) in items.iter() {
+        if collisions.get(entity, character_entity).is_some() {
+            info!("Picked up the {key} key");
+
+            keys.0.insert(key.clone());
+            commands.entity(entity).despawn_recursive();
+        }
+    }
+}
+
+fn unlock_doors(
+    keys: Res<Keys>,
+    mut doors: Query<(&DoorDefinition, &DoorSlide, &mut DoorState), With<Door>>,
+    beacons: Query<&BeaconState>,
+    enemies: Query<&Transform, With<Enemy>>,
+) {
+    for (definition, slide, mut state) in doors.iter_mut() {
+        if *state != DoorState::Locked {
+            continue;
+        }
+
+        let unlocked = match &definition.lock {
+            None => true,
+            Some(DoorLock::Key { key }) => keys.0.contains(key),
+            Some(DoorLock::BeaconsOnline { count }) => {
+                beacons
+                    .iter()
+                    .filter(|state| **state == BeaconState::Online)
+                    .count()
+                    >= *count
+            }
+            Some(DoorLock::RoomCleared { radius }) => !enemies
+                .iter()
+                .any(|enemy| enemy.translation.distance(slide.closed) <= *radius),
+        };
+
+        if unlocked {
+            info!("Door unlocked");
+            *state = DoorState::Closed;
+        }
+    }
+}
+
 fn trigger_door(
     mut commands: Commands,
     collisions: Res<Collisions>,
     character: Query<Entity, With<CharacterController>>,
-    door_triggers: Query<(Entity, &DoorTrigger,)>,
+    door_triggers: Query<(Entity, &DoorTrigger)>,
+    mut doors: Query<&mut DoorState, With<Door>>,
 ) {
-    let character_entity = character.single();
+    let Ok(character_entity) = character.get_single() else {
+        return;
+    };
 
     for (entity, DoorTrigger(door)) in &door_triggers {
-        if let Some(_) = collisions.get(entity, character_entity) {
-            println!("Open the door");
-            commands.entity(*door).despawn_recursive();
+        if collisions.get(entity, character_entity).is_none() {
+            continue;
+        }
+
+        let Ok(mut state) = doors.get_mut(*door) else {
+            continue;
+        };
+
+        if *state == DoorState::Closed {
+            info!("Door opened");
+
+            *state = DoorState::Opening;
             commands.entity(entity).despawn_recursive();
         }
     }
 }
+
+fn slide_doors(
+    time: Res<Time>,
+    mut doors: Query<(&DoorDefinition, &mut DoorSlide, &mut DoorState, &mut Transform), With<Door>>,
+) {
+    for (definition, mut slide, mut state, mut transform) in doors.iter_mut() {
+        if *state != DoorState::Opening {
+            continue;
+        }
+
+        slide.timer.tick(time.delta());
+
+        // ease in and out, so the door doesn't start or stop with a jolt.
+        let t = slide.timer.percent();
+        let eased = t * t * (3.0 - 2.0 * t);
+
+        transform.translation = slide.closed + Vec3::from_array(definition.slide) * eased;
+
+        if slide.timer.finished() {
+            *state = DoorState::Open;
+        }
+    }
+}
+
+/// Finds the nodes of the level's scene named after each door, once the scene has spawned. Only
+/// nodes are moved, the meshes under them follow along.
+fn link_door_visuals(
+    mut commands: Commands,
+    doors: Query<(Entity, &Name), With<Door>>,
+    nodes: Query<
+        (Entity, &Name, &Transform),
+        (Added<Name>, Without<Door>, Without<DoorVisual>, Without<Handle<Mesh>>),
+    >,
+) {
+    for (node, node_name, transform) in nodes.iter() {
+        if let Some((door, _)) = doors.iter().find(|(_, name)| name.as_str() == node_name.as_str()) {
+            commands.entity(node).insert(DoorVisual {
+                door,
+                closed: transform.translation,
+            });
+        }
+    }
+}
+
+fn move_door_visuals(
+    doors: Query<(&Transform, &DoorSlide), With<Door>>,
+    mut visuals: Query<(&DoorVisual, &mut Transform), Without<Door>>,
+) {
+    for (visual, mut transform) in visuals.iter_mut() {
+        let Ok((door_transform, slide)) = doors.get(visual.door) else {
+            continue;
+        };
+
+        transform.translation = visual.closed + (door_transform.translation - slide.closed);
+    }
+}
+
+fn show_locked_doors(
+    door_triggers: Query<(&Transform, &DoorTrigger)>,
+    doors: Query<(&DoorState, &DoorDefinition), With<Door>>,
+    mut gizmos: Gizmos,
+) {
+    for (transform, DoorTrigger(door)) in door_triggers.iter() {
+        let Ok((state, definition)) = doors.get(*door) else {
+            continue;
+        };
+
+        if *state == DoorState::Locked {
+            gizmos.cuboid(
+                transform.with_scale(transform.scale * Vec3::from_array(definition.trigger_size)),
+                Color::RED,
+            );
+        }
+    }
+}
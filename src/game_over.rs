@@ -1,7 +1,7 @@
 use bevy::{app::AppExit, prelude::*};
 use bevy_mod_picking::prelude::*;
 
-use crate::{cleanup, levels::Levels, GameState};
+use crate::{cleanup, levels::Levels, main_menu::spawn_button, GameState};
 
 #[derive(Debug, Resource, PartialEq, Eq, Copy, Clone)]
 pub enum Outcome {
@@ -183,6 +183,16 @@ fn spawn_gameover_menu(
         })
         .set_parent(root);
 
+    spawn_button(
+        &mut commands,
+        &asset_server,
+        root,
+        "MAIN MENU",
+        On::<Pointer<Click>>::run(|mut next_state: ResMut<NextState<GameState>>| {
+            next_state.set(GameState::MainMenu)
+        }),
+    );
+
     commands
         .spawn((
             ButtonBundle {
@@ -296,6 +306,16 @@ fn spawn_pause_menu(mut commands: Commands, asset_server: Res<AssetServer>) {
         })
         .set_parent(root);
 
+    spawn_button(
+        &mut commands,
+        &asset_server,
+        root,
+        "MAIN MENU",
+        On::<Pointer<Click>>::run(|mut next_state: ResMut<NextState<GameState>>| {
+            next_state.set(GameState::MainMenu)
+        }),
+    );
+
     commands
         .spawn((
             ButtonBundle {
@@ -1,21 +1,32 @@
-use bevy::{ecs::system::SystemId, prelude::*};
+use bevy::{gltf::GltfExtras, prelude::*};
+use bevy_xpbd_3d::prelude::{Collider, Collisions, RigidBody, Sensor};
+use serde::Deserialize;
 
-use crate::{cleanup, controller::CharacterController, Enemy, GameState, InGame, KillCount, game_over::Outcome};
+use crate::{controller::CharacterController, death::LootDropped, GameState, InGame};
 
 pub struct HealthPlugin;
 
 impl Plugin for HealthPlugin {
     fn build(&self, app: &mut App) {
-        app.add_event::<UpdateHealth>();
+        app.add_event::<DamageEvent>()
+            .add_event::<HealEvent>()
+            .add_event::<Died>();
         app.add_systems(OnEnter(GameState::BeginGame), spawn_player_health);
         app.add_systems(
             Update,
-            (update_healths, update_player_health)
+            (
+                tick_invulnerability,
+                spawn_dropped_health_packs,
+                pick_up_health_packs,
+                update_healths,
+                regenerate_health,
+                decay_overheal,
+                spin_health_packs,
+                update_player_health,
+            )
                 .chain()
                 .run_if(in_state(GameState::Playing)),
         );
-
-        //app.add_systems(OnExit(GameState::Playing), cleanup::<>);
     }
 }
 
@@ -32,44 +43,382 @@ impl Health {
             amount,
         }
     }
+
+    /// How far over its maximum health can be pushed by overhealing.
+    pub fn overheal_cap(&self) -> f32 {
+        self.max * OVERHEAL_FACTOR
+    }
+}
+
+/// Overhealing can push health up to this many times its maximum.
+const OVERHEAL_FACTOR: f32 = 1.5;
+
+/// How quickly health above the maximum drains away, in health per second.
+const OVERHEAL_DECAY: f32 = 5.0;
+
+/// Slowly restores health once its owner has gone `delay` without taking damage.
+#[derive(Component)]
+pub struct Regeneration {
+    pub per_second: f32,
+    pub delay: Timer,
+}
+
+impl Regeneration {
+    pub fn new(per_second: f32, delay: f32) -> Self {
+        Self {
+            per_second,
+            delay: Timer::from_seconds(delay, TimerMode::Once),
+        }
+    }
+}
+
+/// Restores health to the player when they walk into it. Levels place them with nodes named
+/// `HealthPack`, configured through their extras, e.g. `{ "amount": 100.0, "overheal": true }`.
+#[derive(Component, Deserialize, Clone, Debug)]
+#[serde(default)]
+pub struct HealthPack {
+    pub amount: f32,
+    /// Whether the pack can push the player's health over its maximum.
+    pub overheal: bool,
+}
+
+impl Default for HealthPack {
+    fn default() -> Self {
+        Self {
+            amount: 50.0,
+            overheal: false,
+        }
+    }
 }
 
-//pub struct OnDeath(pub SystemId);
+impl HealthPack {
+    /// Reads a health pack from the extras of its level node, falling back to the defaults for
+    /// anything left out.
+    pub fn from_extras(extras: Option<&GltfExtras>) -> Self {
+        let Some(extras) = extras else {
+            return Self::default();
+        };
+
+        serde_json::from_str(&extras.value).unwrap_or_else(|err| {
+            warn!("invalid health pack {:?}: {err}", extras.value);
+            Self::default()
+        })
+    }
+}
 
-#[derive(Event)]
-pub struct UpdateHealth(pub Entity, pub f32);
+/// Spawns a health pack floating at `translation`.
+pub fn spawn_health_pack(
+    commands: &mut Commands,
+    meshes: &mut Assets<Mesh>,
+    materials: &mut Assets<StandardMaterial>,
+    translation: Vec3,
+    pack: HealthPack,
+) -> Entity {
+    let (color, emissive) = if pack.overheal {
+        (Color::GOLD, Color::rgb(4.0, 3.0, 0.0))
+    } else {
+        (Color::GREEN, Color::rgb(0.0, 4.0, 0.0))
+    };
+
+    commands
+        .spawn((
+            PbrBundle {
+                mesh: meshes.add(shape::Cube { size: 0.6 }.into()),
+                material: materials.add(StandardMaterial {
+                    base_color: color,
+                    emissive,
+                    ..default()
+                }),
+                transform: Transform::from_translation(translation),
+                ..default()
+            },
+            pack,
+            Sensor,
+            RigidBody::Static,
+            Collider::cuboid(1.5, 1.5, 1.5),
+            InGame,
+        ))
+        .id()
+}
+
+/// Sent when something's health runs out. What happens next is up to its
+/// [`DeathBehavior`](crate::death::DeathBehavior).
+#[derive(Event, Debug, Clone, Copy)]
+pub struct Died {
+    pub entity: Entity,
+    /// Whatever dealt the finishing blow, if it's known.
+    pub killer: Option<Entity>,
+    /// Where it died.
+    pub position: Vec3,
+}
+
+/// The kinds of damage something can take, see [`Resistances`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum DamageKind {
+    /// Enemies hitting things up close.
+    Melee,
+    /// Bolts from weapons and turrets.
+    Energy,
+    /// Hazards in the level, like beacon safe zones.
+    Environmental,
+    /// Kills outright, ignoring armor, resistances and invulnerability.
+    Lethal,
+}
+
+/// Damages `target` by `amount`, before its armor and resistances are taken into account.
+#[derive(Event, Debug, Clone)]
+pub struct DamageEvent {
+    pub target: Entity,
+    pub amount: f32,
+    pub kind: DamageKind,
+    /// Whatever dealt the damage, if it's known.
+    pub source: Option<Entity>,
+    /// Where the damage landed, if anywhere in particular.
+    pub point: Option<Vec3>,
+}
+
+impl DamageEvent {
+    pub fn new(target: Entity, amount: f32, kind: DamageKind) -> Self {
+        Self {
+            target,
+            amount,
+            kind,
+            source: None,
+            point: None,
+        }
+    }
+
+    pub fn with_source(mut self, source: Entity) -> Self {
+        self.source = Some(source);
+        self
+    }
+
+    pub fn with_point(mut self, point: Vec3) -> Self {
+        self.point = Some(point);
+        self
+    }
+}
+
+/// Restores `amount` of `target`'s health, up to its maximum.
+#[derive(Event, Debug, Clone)]
+pub struct HealEvent {
+    pub target: Entity,
+    pub amount: f32,
+    /// Whether the heal can go past the maximum, up to [`Health::overheal_cap`]. Anything over the
+    /// maximum drains away over time.
+    pub overheal: bool,
+}
+
+/// Flat damage taken off of every hit, after resistances.
+#[derive(Component, Debug, Clone, Copy)]
+pub struct Armor(pub f32);
+
+/// How much of each kind of damage gets through, e.g. `0.5` halves it and `0.0` ignores it.
+#[derive(Component, Debug, Clone, Copy)]
+pub struct Resistances {
+    pub melee: f32,
+    pub energy: f32,
+    pub environmental: f32,
+}
+
+impl Default for Resistances {
+    fn default() -> Self {
+        Self {
+            melee: 1.0,
+            energy: 1.0,
+            environmental: 1.0,
+        }
+    }
+}
+
+impl Resistances {
+    pub fn multiplier(&self, kind: DamageKind) -> f32 {
+        match kind {
+            DamageKind::Melee => self.melee,
+            DamageKind::Energy => self.energy,
+            DamageKind::Environmental => self.environmental,
+            DamageKind::Lethal => 1.0,
+        }
+    }
+}
+
+/// Ignores any damage for a little while after being hit.
+#[derive(Component)]
+pub struct InvulnerabilityWindow(pub Timer);
+
+impl InvulnerabilityWindow {
+    pub fn new(seconds: f32) -> Self {
+        let mut timer = Timer::from_seconds(seconds, TimerMode::Once);
+        // start out vulnerable.
+        timer.tick(timer.duration());
+
+        Self(timer)
+    }
+
+    pub fn is_active(&self) -> bool {
+        !self.0.finished()
+    }
+}
+
+fn tick_invulnerability(time: Res<Time>, mut windows: Query<&mut InvulnerabilityWindow>) {
+    for mut window in windows.iter_mut() {
+        window.0.tick(time.delta());
+    }
+}
 
 fn update_healths(
-    mut commands: Commands,
-    mut healths: Query<(&mut Health, Option<&Enemy>, Option<&CharacterController>)>,
-    mut health_events: EventReader<UpdateHealth>,
-    mut kill_count: ResMut<KillCount>,
-    mut next_state: ResMut<NextState<GameState>>,
+    mut healths: Query<(
+        &mut Health,
+        Option<&Armor>,
+        Option<&Resistances>,
+        Option<&mut InvulnerabilityWindow>,
+        Option<&mut Regeneration>,
+        Option<&GlobalTransform>,
+    )>,
+    mut damage_events: EventReader<DamageEvent>,
+    mut heal_events: EventReader<HealEvent>,
+    mut died: EventWriter<Died>,
 ) {
-    for UpdateHealth(entity, amount) in health_events.read() {
-        let Ok((mut health, maybe_enemy, maybe_player)) = healths.get_mut(*entity) else { 
-            continue 
+    for HealEvent {
+        target,
+        amount,
+        overheal,
+    } in heal_events.read()
+    {
+        if let Ok((mut health, ..)) = healths.get_mut(*target) {
+            let cap = if *overheal {
+                health.overheal_cap()
+            } else {
+                health.max
+            };
+
+            // regular heals don't take away overheal the target already has.
+            health.amount = health.amount.max((health.amount + amount).min(cap));
+        }
+    }
+
+    for event in damage_events.read() {
+        let Ok((mut health, armor, resistances, invulnerability, regeneration, transform)) =
+            healths.get_mut(event.target)
+        else {
+            continue;
         };
 
-        health.amount = (health.amount + amount).min(health.max);
+        if health.amount <= 0.0 {
+            continue;
+        }
+
+        let amount = if event.kind == DamageKind::Lethal {
+            health.amount
+        } else {
+            if invulnerability.as_ref().is_some_and(|window| window.is_active()) {
+                continue;
+            }
+
+            let resisted = event.amount * resistances.map_or(1.0, |r| r.multiplier(event.kind));
+            (resisted - armor.map_or(0.0, |Armor(armor)| *armor)).max(0.0)
+        };
+
+        if amount <= 0.0 {
+            continue;
+        }
+
+        health.amount = (health.amount - amount).max(0.0);
+
+        if let Some(mut window) = invulnerability {
+            window.0.reset();
+        }
+
+        if let Some(mut regeneration) = regeneration {
+            regeneration.delay.reset();
+        }
 
         if health.amount <= 0.0 {
-            commands.entity(*entity).despawn_recursive();
+            died.send(Died {
+                entity: event.target,
+                killer: event.source,
+                position: transform.map_or(Vec3::ZERO, |transform| transform.translation()),
+            });
+        }
+    }
+}
 
-            if maybe_player.is_some() {
-                println!("Game Over!");
+fn regenerate_health(time: Res<Time>, mut healths: Query<(&mut Health, &mut Regeneration)>) {
+    for (mut health, mut regeneration) in healths.iter_mut() {
+        if health.amount <= 0.0 || health.amount >= health.max {
+            continue;
+        }
 
-                next_state.set(GameState::GameOver);
-                commands.insert_resource(Outcome::Lost);
+        if regeneration.delay.tick(time.delta()).finished() {
+            health.amount =
+                (health.amount + regeneration.per_second * time.delta_seconds()).min(health.max);
+        }
+    }
+}
 
-                return;
-            } else if maybe_enemy.is_some() {
-                kill_count.0 += 1;
-            }
+fn decay_overheal(time: Res<Time>, mut healths: Query<&mut Health>) {
+    for mut health in healths.iter_mut() {
+        if health.amount > health.max {
+            health.amount = (health.amount - OVERHEAL_DECAY * time.delta_seconds()).max(health.max);
         }
     }
 }
 
+fn pick_up_health_packs(
+    mut commands: Commands,
+    collisions: Res<Collisions>,
+    players: Query<(Entity, &Health), With<CharacterController>>,
+    packs: Query<(Entity, &HealthPack)>,
+    mut heals: EventWriter<HealEvent>,
+) {
+    let Ok((player, health)) = players.get_single() else {
+        return;
+    };
+
+    for (entity, pack) in packs.iter() {
+        if collisions.get(entity, player).is_none() {
+            continue;
+        }
+
+        // regular packs are left for later rather than wasted on a player at full health.
+        if health.amount >= health.max && !pack.overheal {
+            continue;
+        }
+
+        heals.send(HealEvent {
+            target: player,
+            amount: pack.amount,
+            overheal: pack.overheal,
+        });
+
+        commands.entity(entity).despawn_recursive();
+    }
+}
+
+/// Enemies that drop loot drop health packs.
+fn spawn_dropped_health_packs(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    mut dropped: EventReader<LootDropped>,
+) {
+    for LootDropped(position) in dropped.read() {
+        spawn_health_pack(
+            &mut commands,
+            &mut meshes,
+            &mut materials,
+            *position,
+            HealthPack::default(),
+        );
+    }
+}
+
+fn spin_health_packs(time: Res<Time>, mut packs: Query<&mut Transform, With<HealthPack>>) {
+    for mut transform in packs.iter_mut() {
+        transform.rotate_y(time.delta_seconds());
+    }
+}
+
 #[derive(Component)]
 pub struct HealthBar;
 
@@ -107,11 +456,21 @@ fn spawn_player_health(mut commands: Commands) {
 }
 
 fn update_player_health(
-    mut health_bars: Query<&mut Style, With<HealthBar>>,
+    mut health_bars: Query<(&mut Style, &mut BackgroundColor), With<HealthBar>>,
     player: Query<&Health, With<CharacterController>>,
 ) {
-    let mut health_bar = health_bars.single_mut();
-    let player_health = player.single();
+    let (Ok((mut health_bar, mut color)), Ok(player_health)) =
+        (health_bars.get_single_mut(), player.get_single())
+    else {
+        return;
+    };
+
+    health_bar.width = Val::Percent((player_health.amount / player_health.max).min(1.0) * 100.0);
 
-    health_bar.width = Val::Percent((player_health.amount / player_health.max) * 100.0);
+    // the bar turns gold while the player is overhealed.
+    *color = if player_health.amount > player_health.max {
+        Color::GOLD.into()
+    } else {
+        Color::rgb_u8(55, 170, 219).into()
+    };
 }
@@ -0,0 +1,346 @@
+use bevy::prelude::*;
+use bevy_mod_picking::prelude::Pickable;
+
+use crate::{
+    controller::CharacterController,
+    health::{DamageEvent, Health},
+    objectives::ObjectiveProgress,
+    triggers::{TriggerAction, TriggerFired},
+    weapon::Ammo,
+    Enemy, GameState, InGame, KillCount,
+};
+
+/// The in-game overlay: crosshair, damage indicators, ammo, kill counter, level timer, wave info and
+/// a vignette when the player is low on health.
+pub struct HudPlugin;
+
+impl Plugin for HudPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Waves>()
+            .add_systems(OnEnter(GameState::BeginGame), (reset_waves, spawn_hud))
+            .add_systems(
+                Update,
+                (
+                    count_waves,
+                    spawn_damage_indicators,
+                    update_damage_indicators,
+                    update_kill_counter,
+                    update_level_timer,
+                    update_wave_info,
+                    update_ammo_counter,
+                    update_vignette,
+                )
+                    .chain()
+                    .run_if(in_state(GameState::Playing)),
+            );
+    }
+}
+
+/// How far from the middle of the screen damage indicators sit, in pixels.
+const INDICATOR_RADIUS: f32 = 90.0;
+
+/// How big a damage indicator is, in pixels.
+const INDICATOR_SIZE: f32 = 14.0;
+
+/// How long a damage indicator takes to fade out, in seconds.
+const INDICATOR_TIME: f32 = 1.2;
+
+/// The fraction of health below which the vignette starts showing.
+const LOW_HEALTH: f32 = 0.3;
+
+/// How many waves triggers have sent at the player this level.
+#[derive(Resource, Default)]
+pub struct Waves(pub usize);
+
+/// The middle of the screen, which the crosshair and damage indicators are laid out around.
+#[derive(Component)]
+pub struct HudCenter;
+
+/// Shows which way a hit came from, fading away once its timer runs out.
+#[derive(Component)]
+pub struct DamageIndicator {
+    /// Where the hit came from.
+    pub from: Vec3,
+    pub timer: Timer,
+}
+
+#[derive(Component)]
+pub struct KillCounter;
+
+#[derive(Component)]
+pub struct LevelTimer;
+
+#[derive(Component)]
+pub struct WaveInfo;
+
+#[derive(Component)]
+pub struct AmmoCounter;
+
+#[derive(Component)]
+pub struct Vignette;
+
+fn reset_waves(mut waves: ResMut<Waves>) {
+    waves.0 = 0;
+}
+
+fn spawn_hud(mut commands: Commands, server: Res<AssetServer>) {
+    // the vignette goes underneath everything else.
+    commands.spawn((
+        NodeBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                width: Val::Percent(100.0),
+                height: Val::Percent(100.0),
+                border: UiRect::all(Val::Px(80.0)),
+                ..default()
+            },
+            border_color: Color::NONE.into(),
+            z_index: ZIndex::Global(-1),
+            ..default()
+        },
+        Vignette,
+        Pickable::IGNORE,
+        InGame,
+    ));
+
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    width: Val::Percent(100.0),
+                    height: Val::Percent(100.0),
+                    justify_content: JustifyContent::Center,
+                    align_items: AlignItems::Center,
+                    ..default()
+                },
+                ..default()
+            },
+            Pickable::IGNORE,
+            InGame,
+        ))
+        .with_children(|parent| {
+            parent
+                .spawn((NodeBundle::default(), HudCenter, Pickable::IGNORE))
+                .with_children(|parent| {
+                    // the crosshair, a gap in the middle so it doesn't cover what's being aimed at.
+                    for (left, top, width, height) in [
+                        (-9.0, -1.0, 6.0, 2.0),
+                        (3.0, -1.0, 6.0, 2.0),
+                        (-1.0, -9.0, 2.0, 6.0),
+                        (-1.0, 3.0, 2.0, 6.0),
+                    ] {
+                        parent.spawn((
+                            NodeBundle {
+                                style: Style {
+                                    position_type: PositionType::Absolute,
+                                    left: Val::Px(left),
+                                    top: Val::Px(top),
+                                    width: Val::Px(width),
+                                    height: Val::Px(height),
+                                    ..default()
+                                },
+                                background_color: Color::WHITE.with_a(0.8).into(),
+                                ..default()
+                            },
+                            Pickable::IGNORE,
+                        ));
+                    }
+                });
+        });
+
+    let style = TextStyle {
+        font_size: 20.,
+        color: Color::rgb_u8(56, 193, 235),
+        font: server.load("fonts/motion-control.bold.otf"),
+    };
+
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    bottom: Val::Px(10.0),
+                    right: Val::Px(10.0),
+                    flex_direction: FlexDirection::Column,
+                    align_items: AlignItems::FlexEnd,
+                    ..default()
+                },
+                ..default()
+            },
+            InGame,
+        ))
+        .with_children(|parent| {
+            parent.spawn((TextBundle::from_section("", style.clone()), AmmoCounter));
+            parent.spawn((TextBundle::from_section("", style.clone()), WaveInfo));
+            parent.spawn((TextBundle::from_section("", style.clone()), KillCounter));
+            parent.spawn((TextBundle::from_section("", style), LevelTimer));
+        });
+}
+
+fn count_waves(mut fired: EventReader<TriggerFired>, mut waves: ResMut<Waves>) {
+    for TriggerFired { action, .. } in fired.read() {
+        if matches!(action, TriggerAction::SpawnWave { .. }) {
+            waves.0 += 1;
+        }
+    }
+}
+
+fn spawn_damage_indicators(
+    mut commands: Commands,
+    mut damage: EventReader<DamageEvent>,
+    player: Query<Entity, With<CharacterController>>,
+    sources: Query<&GlobalTransform>,
+    centers: Query<Entity, With<HudCenter>>,
+) {
+    let (Ok(player), Ok(center)) = (player.get_single(), centers.get_single()) else {
+        return;
+    };
+
+    for event in damage.read().filter(|event| event.target == player) {
+        // prefer whatever dealt the damage, since that's what the player wants to turn towards.
+        let Some(from) = event
+            .source
+            .and_then(|source| sources.get(source).ok())
+            .map(|transform| transform.translation())
+            .or(event.point)
+        else {
+            continue;
+        };
+
+        commands
+            .spawn((
+                NodeBundle {
+                    style: Style {
+                        position_type: PositionType::Absolute,
+                        width: Val::Px(INDICATOR_SIZE),
+                        height: Val::Px(INDICATOR_SIZE),
+                        ..default()
+                    },
+                    background_color: Color::NONE.into(),
+                    ..default()
+                },
+                DamageIndicator {
+                    from,
+                    timer: Timer::from_seconds(INDICATOR_TIME, TimerMode::Once),
+                },
+                Pickable::IGNORE,
+            ))
+            .set_parent(center);
+    }
+}
+
+/// Keeps damage indicators pointing at where the hit came from as the player turns, fading them
+/// out as they go.
+fn update_damage_indicators(
+    time: Res<Time>,
+    mut commands: Commands,
+    players: Query<&Transform, With<CharacterController>>,
+    mut indicators: Query<(
+        Entity,
+        &mut DamageIndicator,
+        &mut Style,
+        &mut BackgroundColor,
+    )>,
+) {
+    let Ok(player) = players.get_single() else {
+        return;
+    };
+
+    let forward = Vec3::new(player.forward().x, 0.0, player.forward().z).normalize_or_zero();
+    let right = Vec3::new(-forward.z, 0.0, forward.x);
+
+    for (entity, mut indicator, mut style, mut color) in indicators.iter_mut() {
+        if indicator.timer.tick(time.delta()).finished() {
+            commands.entity(entity).despawn_recursive();
+            continue;
+        }
+
+        let offset = indicator.from - player.translation;
+        let bearing = offset.dot(right).atan2(offset.dot(forward));
+
+        // straight ahead is the top of the screen.
+        style.left = Val::Px(bearing.sin() * INDICATOR_RADIUS - INDICATOR_SIZE / 2.0);
+        style.top = Val::Px(-bearing.cos() * INDICATOR_RADIUS - INDICATOR_SIZE / 2.0);
+
+        *color = Color::RED.with_a(indicator.timer.percent_left()).into();
+    }
+}
+
+fn update_kill_counter(
+    kill_count: Res<KillCount>,
+    mut counters: Query<&mut Text, With<KillCounter>>,
+) {
+    for mut text in counters.iter_mut() {
+        text.sections[0].value = format!("KILLS {}", kill_count.0);
+    }
+}
+
+fn update_level_timer(
+    progress: Res<ObjectiveProgress>,
+    mut timers: Query<&mut Text, With<LevelTimer>>,
+) {
+    let seconds = progress.elapsed.elapsed_secs() as u32;
+
+    for mut text in timers.iter_mut() {
+        text.sections[0].value = format!("{:02}:{:02}", seconds / 60, seconds % 60);
+    }
+}
+
+fn update_wave_info(
+    waves: Res<Waves>,
+    enemies: Query<(), With<Enemy>>,
+    mut infos: Query<&mut Text, With<WaveInfo>>,
+) {
+    let enemies = enemies.iter().count();
+
+    for mut text in infos.iter_mut() {
+        text.sections[0].value = if waves.0 > 0 {
+            format!("WAVE {}  ENEMIES {enemies}", waves.0)
+        } else {
+            format!("ENEMIES {enemies}")
+        };
+    }
+}
+
+/// Shows the rounds left in the player's weapon, or nothing for weapons that never run dry.
+fn update_ammo_counter(
+    players: Query<Entity, With<CharacterController>>,
+    children: Query<&Children>,
+    weapons: Query<&Ammo>,
+    mut counters: Query<&mut Text, With<AmmoCounter>>,
+) {
+    let Ok(player) = players.get_single() else {
+        return;
+    };
+
+    let ammo = children
+        .iter_descendants(player)
+        .find_map(|descendant| weapons.get(descendant).ok());
+
+    for mut text in counters.iter_mut() {
+        text.sections[0].value = ammo.map_or(String::new(), |ammo| {
+            format!("AMMO {} / {}", ammo.current, ammo.max)
+        });
+    }
+}
+
+/// Reddens the edges of the screen once the player is low on health, pulsing harder the closer
+/// they are to dying.
+fn update_vignette(
+    time: Res<Time>,
+    player: Query<&Health, With<CharacterController>>,
+    mut vignettes: Query<&mut BorderColor, With<Vignette>>,
+) {
+    let Ok(health) = player.get_single() else {
+        return;
+    };
+
+    let fraction = (health.amount / health.max).clamp(0.0, 1.0);
+    let intensity = (1.0 - fraction / LOW_HEALTH).max(0.0);
+    let pulse = 0.75 + 0.25 * (time.elapsed_seconds() * 6.0).sin();
+
+    for mut color in vignettes.iter_mut() {
+        *color = Color::RED.with_a(intensity * pulse * 0.5).into();
+    }
+}
@@ -1,5 +1,7 @@
 use bevy::{prelude::*, gltf::Gltf};
 
+use crate::objectives::Objective;
+
 pub struct LevelsPlugin;
 
 impl Plugin for LevelsPlugin {
@@ -15,4 +17,5 @@ pub struct Levels {
 pub struct Level {
     pub name: String,
     pub scene: Handle<Gltf>,
+    pub objective: Objective,
 }
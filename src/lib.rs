@@ -1,18 +1,30 @@
 pub mod beacon;
 pub mod behavior;
 pub mod camera;
+pub mod checkpoint;
+pub mod compass;
 pub mod controller;
+pub mod death;
 pub mod door;
 pub mod health;
+pub mod hud;
 pub mod levels;
+pub mod objectives;
+pub mod perception;
 pub mod portal;
 pub mod projectile;
+pub mod status_bar;
+pub mod steering;
+pub mod triggers;
+pub mod validation;
 pub mod weapon;
 
 pub mod game_over;
 pub mod loading;
+pub mod main_menu;
 
 use bevy::prelude::*;
+use bevy_xpbd_3d::prelude::PhysicsLayer;
 
 #[derive(Debug, States, Eq, PartialEq, Clone, Default, Hash)]
 pub enum GameState {
@@ -35,6 +47,17 @@ pub struct KillCount(pub usize);
 #[derive(Component)]
 pub struct InGame;
 
+/// The physics layers things collide on. Anything without `CollisionLayers` is on all of them, like
+/// the level itself.
+#[derive(PhysicsLayer)]
+pub enum Layer {
+    Level,
+    /// The player and enemies.
+    Character,
+    /// What's left of dead enemies, which only rests on the level.
+    Corpse,
+}
+
 pub fn cleanup<T: Component + 'static>(mut commands: Commands, query: Query<Entity, With<T>>) {
     for entity in query.iter() {
         commands.entity(entity).despawn_recursive();
@@ -2,6 +2,7 @@ use bevy::{gltf::Gltf, prelude::*};
 
 use crate::{
     levels::{Level, Levels},
+    objectives::Objective,
     GameState,
 };
 
@@ -22,7 +23,7 @@ fn is_done_loading(assets: Res<AssetLoader>) -> bool {
 }
 
 fn get_next_state(mut next_state: ResMut<NextState<GameState>>) {
-    next_state.set(GameState::LoadGame);
+    next_state.set(GameState::MainMenu);
 }
 
 #[derive(Default, Resource)]
@@ -45,6 +46,7 @@ fn load_game_assets(
         assets.items.push(server.load("models/beacon.glb"));
         assets.items.push(server.load("models/portal.glb"));
         assets.items.push(server.load("models/creep.glb"));
+        assets.items.push(server.load("models/red_creep.glb"));
 
         assets.levels.push(server.load("models/Level_0.glb"));
         assets.levels.push(server.load("models/Level_1.glb"));
@@ -59,22 +61,27 @@ fn load_game_assets(
                 Level {
                     name: "Level 0".to_string(),
                     scene: server.load("models/Level_0.glb"),
+                    objective: Objective::default(),
                 },
                 Level {
                     name: "Level 1".to_string(),
                     scene: server.load("models/Level_1.glb"),
+                    objective: Objective::default(),
                 },
                 Level {
                     name: "Level 2".to_string(),
                     scene: server.load("models/Level_2.glb"),
+                    objective: Objective::default(),
                 },
                 Level {
                     name: "Level 3".to_string(),
                     scene: server.load("models/Level_3.glb"),
+                    objective: Objective::default(),
                 },
                 Level {
                     name: "Level 4".to_string(),
                     scene: server.load("models/Level_4.glb"),
+                    objective: Objective::default(),
                 },
             ],
         };
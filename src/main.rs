@@ -13,23 +13,36 @@ use bevy_mod_picking::{
     DefaultPickingPlugins,
 };
 use bevy_xpbd_3d::prelude::{
-    AsyncCollider, AsyncSceneCollider, Collider, ComputedCollider, PhysicsDebugPlugin,
-    PhysicsPlugins, RigidBody, Sensor, ShapeCaster,
+    AsyncCollider, AsyncSceneCollider, Collider, CollisionLayers, ComputedCollider,
+    PhysicsDebugPlugin, PhysicsPlugins, RigidBody, Sensor, ShapeCaster,
 };
 use game::{
-    beacon::{all_beacons_online, Beacon, BeaconPlugin, BeaconState},
+    beacon::{Beacon, BeaconPlugin, BeaconState},
     behavior::{BehaviorBundle, BehaviorPlugin, EnemySpawner, Waypoint, WaypointCache},
     camera::FpsCameraPlugin,
+    checkpoint::CheckpointPlugin,
     cleanup,
+    compass::CompassPlugin,
     controller::{CharacterController, CharacterControllerBundle, CharacterControllerPlugin},
-    door::{Door, DoorPlugin, DoorTrigger},
-    game_over::{GameOverPlugin, Outcome},
-    health::{Health, HealthPlugin},
+    death::{DeathBehavior, DeathPlugin},
+    door::{Door, DoorDefinition, DoorPlugin, DoorSlide, DoorTrigger, KeyItem},
+    game_over::GameOverPlugin,
+    health::{
+        spawn_health_pack, Health, HealthPack, HealthPlugin, InvulnerabilityWindow, Regeneration,
+    },
+    hud::HudPlugin,
     levels::{Level, Levels, LevelsPlugin},
     loading::LoadingPlugin,
-    portal::{Portal, PortalPlugin, PortalState, Projector},
-    projectile::{ProjectilePlugin, Weapon, Damage, Speed},
-    Enemy, GameState, InGame, KillCount, weapon::{AutoFire, FiringRate, WeaponsPlugin},
+    main_menu::MainMenuPlugin,
+    objectives::{Exit, Objective, ObjectivesPlugin},
+    perception::PerceptionPlugin,
+    portal::{Dormant, Portal, PortalDefinition, PortalId, PortalPlugin, PortalState, Projector},
+    projectile::{Damage, ProjectilePlugin, Speed, Weapon},
+    status_bar::StatusBarPlugin,
+    triggers::{Trigger, TriggerAction, TriggerPlugin},
+    validation::ValidationPlugin,
+    weapon::{Ammo, AutoFire, FiringRate, WeaponsPlugin},
+    Enemy, GameState, InGame, KillCount, Layer,
 };
 
 /*
@@ -370,12 +383,6 @@ fn setup_game(
     ));*/
 }*/
 
-fn go_to_gameover(mut commands: Commands, mut next_state: ResMut<NextState<GameState>>) {
-    next_state.set(GameState::GameOver);
-
-    commands.insert_resource(Outcome::Won);
-}
-
 fn pause_game(keyboard: Res<Input<KeyCode>>, mut next_state: ResMut<NextState<GameState>>) {
     //println!("next state!");
     if keyboard.pressed(KeyCode::Return) {
@@ -406,7 +413,14 @@ fn setup_game(
     server: Res<AssetServer>,
 ) {
     //info!("AGGGHHHH!");
-    if let Some(gltf) = gltfs.get(&levels.data[levels.current_level].scene) {
+    let level = &levels.data[levels.current_level];
+
+    if let Some(gltf) = gltfs.get(&level.scene) {
+        let mut objective = level.objective.clone();
+
+        // the previous level's waypoints would otherwise lead enemies off into nowhere.
+        waypoint_cache.0.clear();
+
         commands.spawn((
             SceneBundle {
                 scene: gltf.scenes[0].clone(),
@@ -424,9 +438,16 @@ fn setup_game(
         {
             println!("{name:?}");
 
+            // glTF names are unique, so levels with more than one door or collider tell them apart
+            // with a suffix, e.g. `Door.001` or `Collider_Walls`.
             match &name[..] {
-                "Door" => {
-                    let gltf_mesh = gltf_meshes.get(node.mesh.as_ref().unwrap()).unwrap();
+                name if name.starts_with("Door") => {
+                    let Some(gltf_mesh) = node.mesh.as_ref().and_then(|mesh| gltf_meshes.get(mesh))
+                    else {
+                        warn!("door {name:?} has no mesh");
+                        continue;
+                    };
+                    let definition = DoorDefinition::from_extras(node.extras.as_ref());
 
                     let entity = commands
                         .spawn((
@@ -437,33 +458,47 @@ fn setup_game(
                                 ..default()
                             },
                             Door,
+                            Name::new(name.to_string()),
+                            definition.initial_state(),
+                            DoorSlide::new(node.transform.translation, definition.open_time),
+                            definition.clone(),
                             RigidBody::Static,
                             AsyncCollider(ComputedCollider::TriMesh),
+                            InGame,
                         ))
                         .id();
 
+                    let trigger_size = Vec3::from_array(definition.trigger_size);
+
                     commands.spawn((
                         Sensor,
                         RigidBody::Static,
                         DoorTrigger(entity),
-                        Collider::cuboid(3.0, 3.0, 3.0),
+                        Collider::cuboid(trigger_size.x, trigger_size.y, trigger_size.z),
                         SpatialBundle::from_transform(node.transform),
-                    ));
-                }
-                "Collider" => {
-                    let gltf_mesh = gltf_meshes.get(node.mesh.as_ref().unwrap()).unwrap();
-
-                    commands.spawn((
-                        PbrBundle {
-                            mesh: gltf_mesh.primitives[0].mesh.clone(),
-                            material: materials.add(Color::NONE.into()),
-                            ..default()
-                        },
-                        RigidBody::Static,
-                        AsyncCollider(ComputedCollider::TriMesh),
                         InGame,
                     ));
                 }
+                name if name.starts_with("Collider") => {
+                    let Some(gltf_mesh) = node.mesh.as_ref().and_then(|mesh| gltf_meshes.get(mesh))
+                    else {
+                        warn!("collider {name:?} has no mesh");
+                        continue;
+                    };
+
+                    for primitive in gltf_mesh.primitives.iter() {
+                        commands.spawn((
+                            PbrBundle {
+                                mesh: primitive.mesh.clone(),
+                                material: materials.add(Color::NONE.into()),
+                                ..default()
+                            },
+                            RigidBody::Static,
+                            AsyncCollider(ComputedCollider::TriMesh),
+                            InGame,
+                        ));
+                    }
+                }
                 "SpawnPoint" => {
                     let mut collider = Collider::capsule(2.0, 0.4);
                     collider.set_scale(Vec3::ONE * 0.99, 10);
@@ -492,8 +527,15 @@ fn setup_game(
                                 ..Default::default()
                             },
                             Health::new(350.0),
+                            InvulnerabilityWindow::new(0.5),
+                            Regeneration::new(4.0, 6.0),
+                            DeathBehavior::Remain,
                             Collider::capsule(1.0, 0.5),
                             RigidBody::Kinematic,
+                            CollisionLayers::new(
+                                [Layer::Character],
+                                [Layer::Level, Layer::Character],
+                            ),
                             ShapeCaster::new(collider, Vec3::ZERO, Quat::default(), Vec3::NEG_Y)
                                 .with_max_time_of_impact(0.2),
                             InGame,
@@ -513,6 +555,7 @@ fn setup_game(
                             FiringRate(240.0),
                             Damage(60.0),
                             Speed(25.0),
+                            Ammo::new(120),
                             Name::new("Yon weapon of choice"),
                             InGame,
                         ))
@@ -533,26 +576,122 @@ fn setup_game(
                         ..default()
                     },
                     Projector,
+                    Health::new(200.0),
                     RigidBody::Static,
                     Collider::capsule(2.6, 2.0),
                     InGame,
                 ));
             }
 
-            if name.contains("Portal") {
+            if name.contains("Objective") {
+                objective = Objective::from_extras(node.extras.as_ref(), &objective);
+            }
+
+            if name.contains("Key") {
+                if let Some(key) = KeyItem::from_extras(node.extras.as_ref()) {
+                    commands.spawn((
+                        PbrBundle {
+                            mesh: meshes.add(shape::Cube { size: 0.5 }.into()),
+                            material: materials.add(StandardMaterial {
+                                base_color: Color::YELLOW,
+                                emissive: Color::rgb(4.0, 4.0, 0.0),
+                                ..default()
+                            }),
+                            transform: node.transform,
+                            ..default()
+                        },
+                        key,
+                        Sensor,
+                        RigidBody::Static,
+                        Collider::cuboid(1.0, 1.0, 1.0),
+                        InGame,
+                    ));
+                }
+            }
+
+            if name.contains("Trigger") {
+                if let Some(trigger) = Trigger::from_extras(node.extras.as_ref()) {
+                    let size = Vec3::from_array(trigger.size);
+
+                    commands.spawn((
+                        Sensor,
+                        RigidBody::Static,
+                        Collider::cuboid(size.x, size.y, size.z),
+                        SpatialBundle::from_transform(node.transform),
+                        trigger,
+                        InGame,
+                    ));
+                }
+            }
+
+            if name.contains("Checkpoint") {
                 commands.spawn((
-                    SceneBundle {
-                        scene: server.load("models/portal.glb#Scene0"),
-                        transform: node.transform,
+                    Sensor,
+                    RigidBody::Static,
+                    Collider::cuboid(3.0, 3.0, 3.0),
+                    SpatialBundle::from_transform(node.transform),
+                    Trigger {
+                        actions: vec![TriggerAction::SetCheckpoint],
                         ..default()
                     },
-                    Portal,
-                    PortalState::default(),
-                    EnemySpawner(Timer::from_seconds(1.0, TimerMode::Repeating)),
                     InGame,
                 ));
             }
 
+            if name.contains("HealthPack") {
+                spawn_health_pack(
+                    &mut commands,
+                    &mut meshes,
+                    &mut materials,
+                    node.transform.translation,
+                    HealthPack::from_extras(node.extras.as_ref()),
+                );
+            }
+
+            if name.contains("Exit") {
+                commands.spawn((
+                    Sensor,
+                    RigidBody::Static,
+                    Exit,
+                    Collider::cuboid(3.0, 3.0, 3.0),
+                    SpatialBundle::from_transform(node.transform),
+                    InGame,
+                ));
+            }
+
+            if name.contains("Portal") {
+                let definition = PortalDefinition::from_extras(node.extras.as_ref());
+                let dormant = definition.dormant;
+
+                let entity = commands
+                    .spawn((
+                        SceneBundle {
+                            scene: server.load("models/portal.glb#Scene0"),
+                            transform: node.transform,
+                            ..default()
+                        },
+                        Portal,
+                        PortalState::default(),
+                        EnemySpawner(Timer::from_seconds(1.0, TimerMode::Repeating)),
+                        Health::new(definition.health),
+                        DeathBehavior::Remain,
+                        definition,
+                        Sensor,
+                        RigidBody::Static,
+                        Collider::cylinder(5.0, 2.5),
+                        InGame,
+                    ))
+                    .id();
+
+                if let Some(id) = PortalId::from_extras(node.extras.as_ref()) {
+                    commands.entity(entity).insert(id);
+                }
+
+                if dormant {
+                    commands.entity(entity).insert(Dormant);
+                }
+            }
+
             if name.contains("Beacon") {
                 commands.spawn((
                     SceneBundle {
@@ -565,12 +704,10 @@ fn setup_game(
                         .with_rotation(node.transform.rotation),
                         ..default()
                     },
-                    Beacon {
-                        activation_radius: 5.0,
-                        safe_zone_radius: 10.0,
-                        heal_factor: 5.0,
-                    },
+                    Beacon::from_extras(node.extras.as_ref()),
                     BeaconState::Offline,
+                    Health::new(150.0),
+                    DeathBehavior::Remain,
                 ));
             }
         }
@@ -580,6 +717,8 @@ fn setup_game(
             color: Color::WHITE,
         });
 
+        commands.insert_resource(objective);
+
         next_state.set(GameState::BeginGame);
 
         for (current_offset, current_pos) in waypoints.iter().enumerate() {
@@ -626,31 +765,33 @@ fn main() {
             AutoGenerateOutlineNormalsPlugin,
         ),
         //PhysicsDebugPlugin::default(),
-        CharacterControllerPlugin,
-        FpsCameraPlugin,
-        ProjectilePlugin,
-        BehaviorPlugin,
-        HealthPlugin,
-        DoorPlugin,
-        BeaconPlugin,
-        PortalPlugin,
-        GameOverPlugin,
-        LevelsPlugin,
-        LoadingPlugin,
-        WeaponsPlugin,
+        (
+            CharacterControllerPlugin,
+            FpsCameraPlugin,
+            ProjectilePlugin,
+            WeaponsPlugin,
+            BehaviorPlugin,
+            PerceptionPlugin,
+            HealthPlugin,
+            DeathPlugin,
+        ),
+        (DoorPlugin, BeaconPlugin, PortalPlugin, ObjectivesPlugin, CompassPlugin, TriggerPlugin),
+        (
+            GameOverPlugin,
+            LevelsPlugin,
+            LoadingPlugin,
+            ValidationPlugin,
+            CheckpointPlugin,
+            StatusBarPlugin,
+            HudPlugin,
+            MainMenuPlugin,
+        ),
     ));
 
     //app.add_plugins((LevelsPlugin, LoadingPlugin));
     //app.add_systems(Startup, setup);
     app.add_systems(Update, (setup_game,).run_if(in_state(GameState::LoadGame)));
     //app.add_systems(OnEnter(GameState::BeginGame), setup_game);
-    app.add_systems(
-        Update,
-        go_to_gameover
-            .run_if(in_state(GameState::Playing))
-            .run_if(all_beacons_online),
-    );
-
     app.add_systems(
         Update,
         start_gameplay.run_if(in_state(GameState::BeginGame)),
@@ -660,6 +801,8 @@ fn main() {
     app.add_systems(Update, unpause_game.run_if(in_state(GameState::Paused)));
 
     app.add_systems(OnEnter(GameState::GameOver), cleanup::<InGame>);
+    // quitting to the main menu from the pause menu leaves the level behind.
+    app.add_systems(OnEnter(GameState::MainMenu), cleanup::<InGame>);
 
     #[cfg(not(release))]
     {
@@ -0,0 +1,357 @@
+use bevy::{app::AppExit, core_pipeline::bloom::BloomSettings, prelude::*};
+use bevy_mod_picking::prelude::*;
+
+use crate::{
+    camera::FpsCamera,
+    cleanup,
+    game_over::{Button, Root},
+    levels::Levels,
+    GameState, KillCount,
+};
+
+/// The menu the game starts on, where a level can be picked and settings changed.
+pub struct MainMenuPlugin;
+
+impl Plugin for MainMenuPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<MenuPage>()
+            .init_resource::<Settings>()
+            .add_systems(OnEnter(GameState::MainMenu), open_main_menu)
+            .add_systems(OnExit(GameState::MainMenu), cleanup::<Root>)
+            .add_systems(
+                Update,
+                (
+                    show_menu_page.run_if(resource_changed::<MenuPage>()),
+                    update_setting_labels,
+                )
+                    .chain()
+                    .run_if(in_state(GameState::MainMenu)),
+            )
+            .add_systems(
+                Update,
+                apply_settings.run_if(resource_changed::<Settings>()),
+            );
+    }
+}
+
+/// Which page of the main menu is showing.
+#[derive(Resource, Default, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum MenuPage {
+    #[default]
+    Title,
+    LevelSelect,
+    Settings,
+}
+
+/// Player settings, changed from the main menu.
+#[derive(Resource, Clone, Debug)]
+pub struct Settings {
+    /// The volume every sound plays at, from 0 to 1.
+    pub volume: f32,
+    /// The camera's vertical field of view, in degrees.
+    pub fov: f32,
+    pub bloom: bool,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            volume: 1.0,
+            fov: 45.0,
+            bloom: true,
+        }
+    }
+}
+
+impl Settings {
+    /// Steps `setting` on to its next value, wrapping back around after the last one.
+    pub fn cycle(&mut self, setting: Setting) {
+        match setting {
+            Setting::Volume => {
+                self.volume = if self.volume >= 1.0 {
+                    0.0
+                } else {
+                    (self.volume + 0.25).min(1.0)
+                };
+            }
+            Setting::Fov => {
+                self.fov = if self.fov >= 90.0 {
+                    45.0
+                } else {
+                    (self.fov + 15.0).min(90.0)
+                };
+            }
+            Setting::Bloom => self.bloom = !self.bloom,
+        }
+    }
+
+    fn label(&self, setting: Setting) -> String {
+        match setting {
+            Setting::Volume => format!("VOLUME {:.0}%", self.volume * 100.0),
+            Setting::Fov => format!("FOV {:.0}", self.fov),
+            Setting::Bloom => format!("BLOOM {}", if self.bloom { "ON" } else { "OFF" }),
+        }
+    }
+}
+
+/// A setting that can be changed from the settings page.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Setting {
+    Volume,
+    Fov,
+    Bloom,
+}
+
+/// The text of a button showing the current value of a setting.
+#[derive(Component)]
+pub struct SettingLabel(pub Setting);
+
+/// Goes back to the title page, starting a new session.
+fn open_main_menu(mut page: ResMut<MenuPage>, mut kill_count: ResMut<KillCount>) {
+    *page = MenuPage::Title;
+    kill_count.0 = 0;
+}
+
+fn show_menu_page(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    page: Res<MenuPage>,
+    levels: Res<Levels>,
+    roots: Query<Entity, With<Root>>,
+) {
+    for root in roots.iter() {
+        commands.entity(root).despawn_recursive();
+    }
+
+    let wrapper = commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    width: Val::Percent(100.0),
+                    height: Val::Percent(100.0),
+                    justify_content: JustifyContent::Center,
+                    ..default()
+                },
+                background_color: Color::DARK_GRAY.into(),
+                ..default()
+            },
+            Pickable::IGNORE,
+            Root,
+        ))
+        .id();
+
+    let root = commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    justify_content: JustifyContent::Center,
+                    flex_direction: FlexDirection::Column,
+                    ..default()
+                },
+                ..default()
+            },
+            Pickable::IGNORE,
+        ))
+        .set_parent(wrapper)
+        .id();
+
+    let title = match *page {
+        MenuPage::Title => "LIMINAL COMBAT",
+        MenuPage::LevelSelect => "LEVEL SELECT",
+        MenuPage::Settings => "SETTINGS",
+    };
+
+    commands
+        .spawn((
+            TextBundle::from_section(
+                title,
+                TextStyle {
+                    font_size: 72.,
+                    color: Color::WHITE,
+                    font: asset_server.load("fonts/motion-control.bold.otf"),
+                },
+            )
+            .with_style(Style {
+                margin: UiRect {
+                    left: Val::Auto,
+                    right: Val::Auto,
+                    top: Val::Px(42.0),
+                    bottom: Val::Px(25.0),
+                },
+                ..default()
+            }),
+            Pickable::IGNORE,
+        ))
+        .set_parent(root);
+
+    match *page {
+        MenuPage::Title => {
+            spawn_button(
+                &mut commands,
+                &asset_server,
+                root,
+                "START",
+                On::<Pointer<Click>>::run(
+                    |mut next_state: ResMut<NextState<GameState>>, mut levels: ResMut<Levels>| {
+                        levels.current_level = 0;
+                        next_state.set(GameState::LoadGame);
+                    },
+                ),
+            );
+
+            spawn_button(
+                &mut commands,
+                &asset_server,
+                root,
+                "LEVEL SELECT",
+                On::<Pointer<Click>>::run(|mut page: ResMut<MenuPage>| {
+                    *page = MenuPage::LevelSelect;
+                }),
+            );
+
+            spawn_button(
+                &mut commands,
+                &asset_server,
+                root,
+                "SETTINGS",
+                On::<Pointer<Click>>::run(|mut page: ResMut<MenuPage>| {
+                    *page = MenuPage::Settings;
+                }),
+            );
+
+            spawn_button(
+                &mut commands,
+                &asset_server,
+                root,
+                "QUIT",
+                On::<Pointer<Click>>::run(|mut app_exit_events: EventWriter<AppExit>| {
+                    app_exit_events.send(AppExit);
+                }),
+            );
+        }
+        MenuPage::LevelSelect => {
+            for (index, level) in levels.data.iter().enumerate() {
+                spawn_button(
+                    &mut commands,
+                    &asset_server,
+                    root,
+                    level.name.to_uppercase(),
+                    On::<Pointer<Click>>::run(
+                        move |mut next_state: ResMut<NextState<GameState>>,
+                              mut levels: ResMut<Levels>| {
+                            levels.current_level = index;
+                            next_state.set(GameState::LoadGame);
+                        },
+                    ),
+                );
+            }
+
+            spawn_back_button(&mut commands, &asset_server, root);
+        }
+        MenuPage::Settings => {
+            for setting in [Setting::Volume, Setting::Fov, Setting::Bloom] {
+                let label = spawn_button(
+                    &mut commands,
+                    &asset_server,
+                    root,
+                    "",
+                    On::<Pointer<Click>>::run(move |mut settings: ResMut<Settings>| {
+                        settings.cycle(setting);
+                    }),
+                );
+
+                commands.entity(label).insert(SettingLabel(setting));
+            }
+
+            spawn_back_button(&mut commands, &asset_server, root);
+        }
+    }
+}
+
+/// Spawns a menu button under `parent`, returning the entity of its text.
+pub(crate) fn spawn_button(
+    commands: &mut Commands,
+    asset_server: &AssetServer,
+    parent: Entity,
+    label: impl Into<String>,
+    on_click: On<Pointer<Click>>,
+) -> Entity {
+    let text = commands
+        .spawn((
+            TextBundle::from_section(
+                label,
+                TextStyle {
+                    font_size: 42.,
+                    color: Color::WHITE,
+                    font: asset_server.load("fonts/motion-control.bold.otf"),
+                },
+            )
+            .with_style(Style {
+                margin: UiRect::horizontal(Val::Auto),
+                ..default()
+            }),
+            Pickable::IGNORE,
+        ))
+        .id();
+
+    commands
+        .spawn((
+            ButtonBundle {
+                background_color: Color::rgb_u8(209, 191, 29).into(),
+                style: Style {
+                    padding: UiRect::all(Val::Px(10.0)),
+                    margin: UiRect::axes(Val::Auto, Val::Px(5.0)),
+                    max_height: Val::Px(100.0),
+                    min_width: Val::Px(242.0),
+                    ..default()
+                },
+                ..default()
+            },
+            on_click,
+            Button,
+        ))
+        .add_child(text)
+        .set_parent(parent);
+
+    text
+}
+
+fn spawn_back_button(commands: &mut Commands, asset_server: &AssetServer, parent: Entity) {
+    spawn_button(
+        commands,
+        asset_server,
+        parent,
+        "BACK",
+        On::<Pointer<Click>>::run(|mut page: ResMut<MenuPage>| {
+            *page = MenuPage::Title;
+        }),
+    );
+}
+
+fn update_setting_labels(settings: Res<Settings>, mut labels: Query<(&SettingLabel, &mut Text)>) {
+    for (SettingLabel(setting), mut text) in labels.iter_mut() {
+        text.sections[0].value = settings.label(*setting);
+    }
+}
+
+fn apply_settings(
+    mut commands: Commands,
+    settings: Res<Settings>,
+    mut volume: ResMut<GlobalVolume>,
+    mut cameras: Query<(Entity, &mut Projection), With<FpsCamera>>,
+) {
+    // only sounds started from now on pick up the new volume.
+    *volume = GlobalVolume::new(settings.volume);
+
+    for (camera, mut projection) in cameras.iter_mut() {
+        if let Projection::Perspective(perspective) = projection.as_mut() {
+            perspective.fov = settings.fov.to_radians();
+        }
+
+        if settings.bloom {
+            commands.entity(camera).insert(BloomSettings::default());
+        } else {
+            commands.entity(camera).remove::<BloomSettings>();
+        }
+    }
+}
@@ -0,0 +1,228 @@
+use bevy::{gltf::GltfExtras, prelude::*, time::Stopwatch};
+use bevy_xpbd_3d::prelude::Collisions;
+use serde::Deserialize;
+
+use crate::{
+    beacon::BeaconState, controller::CharacterController, game_over::Outcome, portal::PortalState,
+    GameState, InGame, KillCount,
+};
+
+pub struct ObjectivesPlugin;
+
+impl Plugin for ObjectivesPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            OnEnter(GameState::BeginGame),
+            (start_objective, spawn_objective_ui),
+        )
+        .add_systems(
+            Update,
+            (tick_objective, check_objective, update_objective_ui)
+                .chain()
+                .run_if(in_state(GameState::Playing)),
+        );
+    }
+}
+
+/// What the player has to do to beat a level.
+#[derive(Deserialize, Clone, Debug, Default)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum WinCondition {
+    /// Bring every beacon in the level online.
+    #[default]
+    AllBeaconsOnline,
+    /// Close at least `count` portals.
+    ClosePortals { count: usize },
+    /// Stay alive for `seconds`.
+    Survive { seconds: f32 },
+    /// Make it to the level's exit.
+    ReachExit,
+    /// Kill `target` enemies.
+    KillCount { target: usize },
+}
+
+/// A level's win condition, along with how long the player has to meet it.
+///
+/// Levels can override their objective with the extras of a node named `Objective`, e.g.
+/// `{ "win": { "type": "survive", "seconds": 120.0 }, "time_limit": 300.0, "lives": 1 }`.
+#[derive(Resource, Deserialize, Clone, Debug)]
+#[serde(default)]
+pub struct Objective {
+    pub win: WinCondition,
+    /// The player loses once this many seconds have passed without winning.
+    pub time_limit: Option<f32>,
+    /// How many times the player can come back at their last checkpoint before losing.
+    pub lives: u32,
+}
+
+impl Default for Objective {
+    fn default() -> Self {
+        Self {
+            win: WinCondition::default(),
+            time_limit: None,
+            lives: 3,
+        }
+    }
+}
+
+impl Objective {
+    /// Reads an objective from the extras of a level node, falling back to `fallback` if they
+    /// can't be read.
+    pub fn from_extras(extras: Option<&GltfExtras>, fallback: &Objective) -> Self {
+        let Some(extras) = extras else {
+            return fallback.clone();
+        };
+
+        serde_json::from_str(&extras.value).unwrap_or_else(|err| {
+            warn!("invalid objective {:?}: {err}", extras.value);
+            fallback.clone()
+        })
+    }
+}
+
+/// How far the player has gotten towards the current level's objective.
+#[derive(Resource, Default)]
+pub struct ObjectiveProgress {
+    /// How long the level has been played for, not counting time spent paused.
+    pub elapsed: Stopwatch,
+    /// The kill count when the level started.
+    pub kills_at_start: usize,
+}
+
+/// Marks the sensor the player has to reach for [`WinCondition::ReachExit`].
+#[derive(Component)]
+pub struct Exit;
+
+fn start_objective(mut commands: Commands, kill_count: Res<KillCount>) {
+    commands.insert_resource(ObjectiveProgress {
+        elapsed: Stopwatch::new(),
+        kills_at_start: kill_count.0,
+    });
+}
+
+fn tick_objective(time: Res<Time>, mut progress: ResMut<ObjectiveProgress>) {
+    progress.elapsed.tick(time.delta());
+}
+
+fn check_objective(
+    mut commands: Commands,
+    mut next_state: ResMut<NextState<GameState>>,
+    objective: Res<Objective>,
+    progress: Res<ObjectiveProgress>,
+    kill_count: Res<KillCount>,
+    collisions: Res<Collisions>,
+    beacons: Query<&BeaconState>,
+    portals: Query<&PortalState>,
+    players: Query<Entity, With<CharacterController>>,
+    exits: Query<Entity, With<Exit>>,
+) {
+    let Ok(player) = players.get_single() else {
+        return;
+    };
+
+    let elapsed = progress.elapsed.elapsed_secs();
+
+    let won = match objective.win {
+        // a level without beacons can't be won by bringing them online.
+        WinCondition::AllBeaconsOnline => {
+            !beacons.is_empty() && beacons.iter().all(|state| *state == BeaconState::Online)
+        }
+        WinCondition::ClosePortals { count } => {
+            portals
+                .iter()
+                .filter(|state| **state == PortalState::Closed)
+                .count()
+                >= count
+        }
+        WinCondition::Survive { seconds } => elapsed >= seconds,
+        WinCondition::ReachExit => exits
+            .iter()
+            .any(|exit| collisions.get(exit, player).is_some()),
+        WinCondition::KillCount { target } => {
+            kill_count.0.saturating_sub(progress.kills_at_start) >= target
+        }
+    };
+
+    let outcome = if won {
+        Outcome::Won
+    } else if objective.time_limit.is_some_and(|limit| elapsed >= limit) {
+        Outcome::Lost
+    } else {
+        return;
+    };
+
+    next_state.set(GameState::GameOver);
+    commands.insert_resource(outcome);
+}
+
+#[derive(Component)]
+pub struct ObjectiveDisplay;
+
+fn spawn_objective_ui(mut commands: Commands, server: Res<AssetServer>) {
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    width: Val::Percent(100.0),
+                    top: Val::Px(5.0),
+                    justify_content: JustifyContent::Center,
+                    ..default()
+                },
+                ..default()
+            },
+            InGame,
+        ))
+        .with_children(|parent| {
+            parent.spawn((
+                TextBundle::from_section(
+                    "",
+                    TextStyle {
+                        font_size: 24.,
+                        color: Color::rgb_u8(56, 193, 235),
+                        font: server.load("fonts/motion-control.bold.otf"),
+                    },
+                ),
+                ObjectiveDisplay,
+            ));
+        });
+}
+
+fn update_objective_ui(
+    mut displays: Query<&mut Text, With<ObjectiveDisplay>>,
+    objective: Res<Objective>,
+    progress: Res<ObjectiveProgress>,
+    kill_count: Res<KillCount>,
+    portals: Query<&PortalState>,
+) {
+    let elapsed = progress.elapsed.elapsed_secs();
+
+    let mut value = match objective.win {
+        WinCondition::AllBeaconsOnline => "BRING ALL BEACONS ONLINE".to_string(),
+        WinCondition::ClosePortals { count } => format!(
+            "CLOSE PORTALS {} / {count}",
+            portals
+                .iter()
+                .filter(|state| **state == PortalState::Closed)
+                .count()
+        ),
+        WinCondition::Survive { seconds } => {
+            format!("SURVIVE {:.0}", (seconds - elapsed).max(0.0).ceil())
+        }
+        WinCondition::ReachExit => "REACH THE EXIT".to_string(),
+        WinCondition::KillCount { target } => {
+            format!(
+                "KILLS {} / {target}",
+                kill_count.0.saturating_sub(progress.kills_at_start)
+            )
+        }
+    };
+
+    if let Some(limit) = objective.time_limit {
+        value += &format!("  -  {:.0}", (limit - elapsed).max(0.0).ceil());
+    }
+
+    for mut text in displays.iter_mut() {
+        text.sections[0].value = value.clone();
+    }
+}
@@ -0,0 +1,160 @@
+use std::f32::consts::PI;
+
+use bevy::prelude::*;
+use bevy_xpbd_3d::prelude::{LinearVelocity, Sensor, SpatialQuery, SpatialQueryFilter};
+
+use crate::{
+    behavior::VisibiltyRange, controller::CharacterController, projectile::Projectile, Enemy,
+    GameState,
+};
+
+pub struct PerceptionPlugin;
+
+impl Plugin for PerceptionPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<Noise>().add_systems(
+            Update,
+            (hear_noises, look_for_player, forget_player)
+                .chain()
+                .in_set(PerceptionSet)
+                .run_if(in_state(GameState::Playing)),
+        );
+    }
+}
+
+/// Systems that update what enemies know about the player. Anything that acts on a [`Perception`]
+/// should run after this.
+#[derive(SystemSet, Debug, Clone, PartialEq, Eq, Hash)]
+pub struct PerceptionSet;
+
+/// A sound that alerts any enemy within `radius` of `position`.
+#[derive(Event)]
+pub struct Noise {
+    pub position: Vec3,
+    pub radius: f32,
+}
+
+/// What an enemy knows about the player.
+#[derive(Component)]
+pub struct Perception {
+    /// Half the angle of the enemy's sight cone, in radians. How far it can see is its
+    /// [`VisibiltyRange`].
+    pub sight_angle: f32,
+    /// Whether the enemy can currently see the player.
+    pub sees_player: bool,
+    /// Where the enemy last saw or heard the player.
+    pub last_known_position: Option<Vec3>,
+    /// How long the enemy keeps hunting the last known position after losing track of the player.
+    pub memory: Timer,
+}
+
+impl Default for Perception {
+    fn default() -> Self {
+        Self {
+            sight_angle: PI / 3.0,
+            sees_player: false,
+            last_known_position: None,
+            memory: Timer::from_seconds(8.0, TimerMode::Once),
+        }
+    }
+}
+
+impl Perception {
+    /// Remembers the player being at `position`.
+    pub fn alert(&mut self, position: Vec3) {
+        self.last_known_position = Some(position);
+        self.memory.reset();
+    }
+
+    /// Forgets where the player was, e.g. once the last known position turned out to be empty.
+    pub fn forget(&mut self) {
+        self.last_known_position = None;
+    }
+}
+
+fn hear_noises(
+    mut noises: EventReader<Noise>,
+    mut enemies: Query<(&Transform, &mut Perception), With<Enemy>>,
+) {
+    for noise in noises.read() {
+        for (transform, mut perception) in enemies.iter_mut() {
+            if transform.translation.distance(noise.position) <= noise.radius {
+                perception.alert(noise.position);
+            }
+        }
+    }
+}
+
+fn look_for_player(
+    spatial_query: SpatialQuery,
+    player: Query<(Entity, &Transform), With<CharacterController>>,
+    mut enemies: Query<
+        (
+            &Transform,
+            &LinearVelocity,
+            &VisibiltyRange,
+            &mut Perception,
+        ),
+        With<Enemy>,
+    >,
+    ignored: Query<Entity, Or<(With<Enemy>, With<Projectile>, With<Sensor>)>>,
+    children: Query<&Children>,
+) {
+    let Ok((player_entity, player_transform)) = player.get_single() else {
+        return;
+    };
+
+    let player_pos = player_transform.translation;
+    // the player's own child colliders would otherwise block the view of the player.
+    let filter = SpatialQueryFilter::new().without_entities(
+        ignored
+            .iter()
+            .chain(children.iter_descendants(player_entity)),
+    );
+
+    for (transform, lin_vel, range, mut perception) in enemies.iter_mut() {
+        let enemy_pos = transform.translation;
+        let to_player = player_pos - enemy_pos;
+        let distance = to_player.length();
+
+        if distance <= f32::EPSILON {
+            perception.sees_player = true;
+            perception.alert(player_pos);
+            continue;
+        }
+
+        // enemies look where they're going.
+        let facing = Vec3::new(lin_vel.x, 0.0, lin_vel.z)
+            .try_normalize()
+            .unwrap_or(transform.forward());
+
+        // straight above or below has no direction to compare with, so it counts as in view.
+        let flat = Vec3::new(to_player.x, 0.0, to_player.z);
+        let in_cone = distance <= range.0
+            && (flat.length_squared() <= f32::EPSILON
+                || facing.angle_between(flat) <= perception.sight_angle);
+
+        perception.sees_player = in_cone
+            && spatial_query
+                .cast_ray(
+                    enemy_pos,
+                    to_player / distance,
+                    distance,
+                    true,
+                    filter.clone(),
+                )
+                .map_or(true, |hit| hit.entity == player_entity);
+
+        if perception.sees_player {
+            perception.alert(player_pos);
+        }
+    }
+}
+
+fn forget_player(time: Res<Time>, mut enemies: Query<&mut Perception, With<Enemy>>) {
+    for mut perception in enemies.iter_mut() {
+        if !perception.sees_player && perception.memory.tick(time.delta()).just_finished() {
+            perception.forget();
+        }
+    }
+}
@@ -1,12 +1,17 @@
-use bevy::prelude::*;
+use std::time::Duration;
+
+use bevy::{gltf::GltfExtras, prelude::*};
 use bevy_xpbd_3d::prelude::*;
 use rand::Rng;
+use serde::Deserialize;
 
 use crate::{
-    beacon::{Beacon, BeaconState},
+    beacon::{Beacon, BeaconOnline, BeaconState},
     behavior::{BehaviorBundle, EnemySpawner},
+    death::{DeathBehavior, DropsLoot},
     health::Health,
-    Enemy, GameState,
+    steering::SteeringWeights,
+    Enemy, GameState, InGame, Layer,
 };
 
 pub struct PortalPlugin;
@@ -15,12 +20,27 @@ impl Plugin for PortalPlugin {
     fn build(&self, app: &mut App) {
         app.register_type::<Portal>()
             .register_type::<PortalId>()
-            .add_systems(OnEnter(GameState::Playing), position_projectors)
+            .add_event::<PortalClosed>()
+            .add_event::<PortalReopened>()
+            .add_systems(
+                OnEnter(GameState::BeginGame),
+                (link_portals, link_projectors, spawn_portal_ui),
+            )
             .add_systems(
                 Update,
                 (
-                    (close_portals, change_portal_color).chain(),
-                    update_enemy_spawners,
+                    (
+                        close_portals.run_if(on_event::<BeaconOnline>()),
+                        destroy_portals,
+                        reopen_portals,
+                        update_closing_portals,
+                        change_portal_color,
+                    )
+                        .chain(),
+                    (weaken_portals, update_enemy_spawners).chain(),
+                    update_projector_beams,
+                    show_portal_links,
+                    update_portal_ui,
                 )
                     .run_if(in_state(GameState::Playing)),
             );
@@ -31,100 +51,687 @@ impl Plugin for PortalPlugin {
 #[reflect(Component)]
 pub struct Portal;
 
+/// Identifies a portal so beacons can be linked to it through [`Beacon::portals`].
 #[derive(Component, Reflect, Default, Debug)]
 #[reflect(Component)]
 pub struct PortalId(pub u32);
 
-#[derive(Component, Default, PartialEq, Eq)]
+impl PortalId {
+    /// Reads a portal's id from the extras of its level node, e.g. `{ "portal_id": 1 }`.
+    pub fn from_extras(extras: Option<&GltfExtras>) -> Option<Self> {
+        #[derive(Deserialize)]
+        struct PortalDefinition {
+            portal_id: Option<u32>,
+        }
+
+        let extras = extras?;
+
+        match serde_json::from_str::<PortalDefinition>(&extras.value) {
+            Ok(definition) => definition.portal_id.map(PortalId),
+            Err(err) => {
+                warn!("invalid portal definition {:?}: {err}", extras.value);
+                None
+            }
+        }
+    }
+}
+
+/// A portal's definition. Levels can override any of it through the extras of the portal's node,
+/// e.g. `{ "portal_id": 1, "health": 800.0, "max_enemies": 6, "reopens": true }`.
+#[derive(Component, Deserialize, Clone, Debug)]
+#[serde(default)]
+pub struct PortalDefinition {
+    /// How much damage the portal can take before it collapses.
+    pub health: f32,
+    /// How many enemies that came out of this portal can be alive at once.
+    pub max_enemies: usize,
+    /// Whether the portal opens back up when one of the beacons that closed it goes offline.
+    pub reopens: bool,
+    /// Whether the portal waits for a trigger to enable it before spawning anything, see
+    /// [`Dormant`].
+    pub dormant: bool,
+    /// The chance of each enemy the portal spawns being a [`CreepKind::RedCreep`].
+    pub red_creep_chance: f32,
+    /// What happens to the portal's creeps when they die. Without one they're just despawned.
+    pub creep_death: Option<DeathBehavior>,
+}
+
+impl Default for PortalDefinition {
+    fn default() -> Self {
+        Self {
+            health: 500.0,
+            max_enemies: 12,
+            reopens: false,
+            dormant: false,
+            red_creep_chance: 0.0,
+            creep_death: None,
+        }
+    }
+}
+
+impl PortalDefinition {
+    /// Reads a portal's definition from the extras of its level node, falling back to the
+    /// defaults for anything left out.
+    pub fn from_extras(extras: Option<&GltfExtras>) -> Self {
+        let Some(extras) = extras else {
+            return Self::default();
+        };
+
+        serde_json::from_str(&extras.value).unwrap_or_else(|err| {
+            warn!("invalid portal definition {:?}: {err}", extras.value);
+            Self::default()
+        })
+    }
+}
+
+/// The beacons that have to be online for a portal to close.
+#[derive(Component, Default, Debug)]
+pub struct LinkedBeacons {
+    pub beacons: Vec<Entity>,
+    /// Whether the level linked the beacons to the portal, rather than them being picked by
+    /// distance.
+    pub explicit: bool,
+}
+
+/// Beacons within this distance of a portal are linked to it when the level doesn't link any
+/// explicitly.
+pub const FALLBACK_LINK_RADIUS: f32 = 30.0;
+
+#[derive(Component, Default, PartialEq, Eq, Copy, Clone, Debug)]
 pub enum PortalState {
     #[default]
     Open,
+    /// The portal has stopped spawning enemies and is collapsing, see [`ClosingTimer`].
+    Closing,
     Closed,
 }
 
+impl PortalState {
+    /// The color portals in this state are shown in.
+    pub fn color(&self) -> Color {
+        match self {
+            PortalState::Open => Color::RED,
+            PortalState::Closing => Color::ORANGE,
+            PortalState::Closed => Color::GREEN,
+        }
+    }
+}
+
+/// How long a portal takes to collapse once it starts closing, in seconds.
+const CLOSING_TIME: f32 = 2.0;
+
+#[derive(Component, Deref, DerefMut)]
+pub struct ClosingTimer(pub Timer);
+
+impl Default for ClosingTimer {
+    fn default() -> Self {
+        Self(Timer::from_seconds(CLOSING_TIME, TimerMode::Once))
+    }
+}
+
+/// Why a portal closed.
+#[derive(Component, Debug, PartialEq, Eq, Copy, Clone)]
+pub enum CloseReason {
+    /// Every beacon linked to the portal came online.
+    Beacons,
+    /// The player shot the portal down.
+    Destroyed,
+}
+
+/// Sent when a portal finishes collapsing.
+#[derive(Event)]
+pub struct PortalClosed {
+    pub portal: Entity,
+    pub reason: CloseReason,
+}
+
+/// Sent when a closed portal opens back up.
+#[derive(Event)]
+pub struct PortalReopened(pub Entity);
+
+/// Keeps a portal from spawning enemies until a level trigger enables it.
+#[derive(Component)]
+pub struct Dormant;
+
+/// The portal an enemy came out of.
+#[derive(Component)]
+pub struct SpawnedBy(pub Entity);
+
 #[derive(Component)]
 pub struct Projector;
 
+/// The portal a projector is powering.
+#[derive(Component)]
+pub struct LinkedPortal(pub Entity);
+
+/// The projectors powering a portal. Every one of them the player destroys slows down how quickly
+/// the portal spawns enemies.
+#[derive(Component)]
+pub struct Projectors {
+    pub projectors: Vec<Entity>,
+    /// How often the portal spawns enemies while all of its projectors are standing.
+    pub base_interval: Duration,
+}
+
+/// The beam a projector shines at its portal while the portal is open.
+#[derive(Component)]
+pub struct ProjectorBeam(pub Entity);
+
+/// Projectors power the nearest portal within this distance.
+pub const PROJECTOR_LINK_RADIUS: f32 = 20.0;
+
 #[derive(Component)]
 pub struct HitTimer(pub Timer, pub Handle<StandardMaterial>);
 
-fn position_projectors(
-    portals: Query<&Transform, With<Portal>>,
-    mut projectors: Query<&mut Transform, (With<Projector>, Without<Portal>)>,
+/// Links every projector to the nearest portal, turns it to face the portal and gives it a beam
+/// to shine at it.
+fn link_projectors(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    portals: Query<(Entity, &Transform, Option<&EnemySpawner>), With<Portal>>,
+    mut projectors: Query<(Entity, &mut Transform), (With<Projector>, Without<Portal>)>,
 ) {
-    //let portal_positions = portals.iter().map(|t| t.translation.distance());
+    let mut powered = Vec::<(Entity, Vec<Entity>)>::new();
 
-    for (mut proj_transform) in projectors.iter_mut() {
-        let nearest_portal = portals
+    for (projector, mut transform) in projectors.iter_mut() {
+        let Some((portal, portal_transform, _)) = portals
             .iter()
-            .filter(|t| t.translation.distance(proj_transform.translation) <= 20.0)
-            .collect::<Vec<_>>()[0];
-
-        let d = nearest_portal.translation - proj_transform.translation;
-        let angle = d.z.atan2(d.x);
-
-        proj_transform.look_at(
-            Vec3::new(
-                nearest_portal.translation.x,
-                0.0,
-                nearest_portal.translation.y,
-            ),
-            Vec3::Y,
-        );
+            .map(|portal| (portal, portal.1.translation.distance(transform.translation)))
+            .filter(|(_, distance)| *distance <= PROJECTOR_LINK_RADIUS)
+            .min_by(|(_, a), (_, b)| a.total_cmp(b))
+            .map(|(portal, _)| portal)
+        else {
+            warn!(
+                "projector {projector:?} has no portal within {PROJECTOR_LINK_RADIUS} units to power"
+            );
+            continue;
+        };
+
+        let target = portal_transform.translation;
+
+        // projectors stay upright, so only turn them around the y axis.
+        transform.look_at(Vec3::new(target.x, transform.translation.y, target.z), Vec3::Y);
+
+        let offset = target - transform.translation;
+        let beam = Transform::from_translation(transform.translation + offset / 2.0)
+            .with_rotation(Quat::from_rotation_arc(Vec3::Y, offset.normalize_or_zero()));
+
+        commands.spawn((
+            PbrBundle {
+                mesh: meshes.add(
+                    shape::Cylinder {
+                        radius: 0.08,
+                        height: offset.length(),
+                        ..default()
+                    }
+                    .into(),
+                ),
+                material: materials.add(StandardMaterial {
+                    base_color: Color::RED,
+                    emissive: Color::rgb(10.0, 0.0, 0.0),
+                    unlit: true,
+                    ..default()
+                }),
+                transform: beam,
+                ..default()
+            },
+            ProjectorBeam(projector),
+            InGame,
+        ));
+
+        commands.entity(projector).insert(LinkedPortal(portal));
+
+        match powered.iter_mut().find(|(entity, _)| *entity == portal) {
+            Some((_, linked)) => linked.push(projector),
+            None => powered.push((portal, vec![projector])),
+        }
+    }
+
+    for (portal, projectors) in powered {
+        let Ok((_, _, spawner)) = portals.get(portal) else {
+            continue;
+        };
+
+        commands.entity(portal).insert(Projectors {
+            projectors,
+            base_interval: spawner.map_or(Duration::from_secs(1), |spawner| spawner.duration()),
+        });
+    }
+}
+
+/// Slows a portal's spawning down by another base interval for every projector of it that's been
+/// destroyed.
+fn weaken_portals(
+    mut portals: Query<(&Projectors, &mut EnemySpawner), With<Portal>>,
+    projectors: Query<(), With<Projector>>,
+) {
+    for (powered_by, mut spawner) in portals.iter_mut() {
+        let destroyed = powered_by
+            .projectors
+            .iter()
+            .filter(|projector| !projectors.contains(**projector))
+            .count();
+
+        let interval = powered_by.base_interval * (destroyed as u32 + 1);
+
+        if spawner.duration() != interval {
+            spawner.set_duration(interval);
+        }
+    }
+}
+
+/// Shows projector beams only while their portal is open, flickering slightly. Beams of destroyed
+/// projectors go away with them.
+fn update_projector_beams(
+    time: Res<Time>,
+    mut commands: Commands,
+    projectors: Query<&LinkedPortal, With<Projector>>,
+    portals: Query<&PortalState, With<Portal>>,
+    mut beams: Query<(Entity, &ProjectorBeam, &mut Visibility, &mut Transform)>,
+) {
+    let flicker = 1.0 + (time.elapsed_seconds() * 30.0).sin() * 0.25;
+
+    for (beam, ProjectorBeam(projector), mut visibility, mut transform) in beams.iter_mut() {
+        let Ok(LinkedPortal(portal)) = projectors.get(*projector) else {
+            commands.entity(beam).despawn_recursive();
+            continue;
+        };
+
+        *visibility = if portals
+            .get(*portal)
+            .is_ok_and(|state| *state == PortalState::Open)
+        {
+            Visibility::Inherited
+        } else {
+            Visibility::Hidden
+        };
+
+        transform.scale = Vec3::new(flicker, 1.0, flicker);
     }
 }
 
 fn update_enemy_spawners(
     time: Res<Time>,
     mut commands: Commands,
-    mut spawners: Query<(&mut EnemySpawner, &Transform, &PortalState)>,
+    mut spawners: Query<
+        (
+            Entity,
+            &mut EnemySpawner,
+            &Transform,
+            &PortalState,
+            Option<&PortalDefinition>,
+        ),
+        Without<Dormant>,
+    >,
+    enemies: Query<&SpawnedBy, With<Enemy>>,
     server: Res<AssetServer>,
 ) {
-    for (mut spawner, transform, _) in spawners
+    for (portal, mut spawner, transform, _, definition) in spawners
         .iter_mut()
-        .filter(|(_, _, state)| **state == PortalState::Open)
+        .filter(|(_, _, _, state, _)| **state == PortalState::Open)
     {
         if spawner.tick(time.delta()).just_finished() {
+            let max_enemies = definition.map_or(usize::MAX, |definition| definition.max_enemies);
+
+            if enemies.iter().filter(|SpawnedBy(e)| *e == portal).count() >= max_enemies {
+                continue;
+            }
+
             let mut rng = rand::thread_rng();
             let rand_y_offset = rng.gen_range(0.0..2.0);
             let rand_x_offset = rng.gen_range(-1.5..1.5);
 
-            commands.spawn((
-                SceneBundle {
-                    scene: server.load("models/creep.glb#Scene0"),
-                    transform: Transform::from_xyz(
-                        transform.translation.x + rand_x_offset,
-                        transform.translation.y + rand_y_offset + 1.5,
-                        transform.translation.z,
-                    ),
-                    ..default()
-                },
-                Enemy,
-                Health::new(100.0),
-                Collider::capsule(0.8, 0.6),
-                RigidBody::Kinematic,
-                BehaviorBundle::default(),
-            ));
+            let red_creep_chance = definition.map_or(0.0, |definition| definition.red_creep_chance);
+            let kind = if rng.gen_bool(red_creep_chance.clamp(0.0, 1.0) as f64) {
+                CreepKind::RedCreep
+            } else {
+                CreepKind::Creep
+            };
+
+            let creep = spawn_creep(
+                &mut commands,
+                &server,
+                kind,
+                Vec3::new(
+                    transform.translation.x + rand_x_offset,
+                    transform.translation.y + rand_y_offset + 1.5,
+                    transform.translation.z,
+                ),
+            );
+
+            commands.entity(creep).insert(SpawnedBy(portal));
+
+            if let Some(death) = definition.and_then(|definition| definition.creep_death) {
+                commands.entity(creep).insert(death);
+            }
+        }
+    }
+}
+
+/// The kinds of enemy portals and triggers can spawn.
+#[derive(Deserialize, Clone, Copy, PartialEq, Eq, Debug, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum CreepKind {
+    #[default]
+    Creep,
+    RedCreep,
+}
+
+impl CreepKind {
+    pub fn scene(&self) -> &'static str {
+        match self {
+            CreepKind::Creep => "models/creep.glb#Scene0",
+            CreepKind::RedCreep => "models/red_creep.glb#Scene0",
         }
     }
+
+    pub fn steering_weights(&self) -> SteeringWeights {
+        match self {
+            CreepKind::Creep => SteeringWeights::CREEP,
+            CreepKind::RedCreep => SteeringWeights::RED_CREEP,
+        }
+    }
+}
+
+/// Spawns a creep of the given `kind` at `translation`.
+pub fn spawn_creep(
+    commands: &mut Commands,
+    server: &AssetServer,
+    kind: CreepKind,
+    translation: Vec3,
+) -> Entity {
+    commands
+        .spawn((
+            SceneBundle {
+                scene: server.load(kind.scene()),
+                transform: Transform::from_translation(translation),
+                ..default()
+            },
+            Enemy,
+            Health::new(100.0),
+            Collider::capsule(0.8, 0.6),
+            RigidBody::Kinematic,
+            CollisionLayers::new([Layer::Character], [Layer::Level, Layer::Character]),
+            BehaviorBundle {
+                steering_weights: kind.steering_weights(),
+                ..default()
+            },
+            DropsLoot { chance: 0.15 },
+        ))
+        .id()
+}
+
+/// Links every portal to the beacons that close it. Levels link beacons to portals by id; levels
+/// that don't fall back to linking the beacons near each portal.
+pub(crate) fn link_portals(
+    mut commands: Commands,
+    portals: Query<(Entity, &Transform, Option<&PortalId>), With<Portal>>,
+    beacons: Query<(Entity, &Transform, &Beacon), Without<Portal>>,
+) {
+    for (portal, portal_transform, id) in portals.iter() {
+        let explicit = id
+            .map(|PortalId(id)| {
+                beacons
+                    .iter()
+                    .filter(|(_, _, beacon)| beacon.portals.contains(id))
+                    .map(|(entity, _, _)| entity)
+                    .collect::<Vec<_>>()
+            })
+            .unwrap_or_default();
+
+        let links = if explicit.is_empty() {
+            LinkedBeacons {
+                beacons: beacons
+                    .iter()
+                    .filter(|(_, transform, beacon)| {
+                        beacon.portals.is_empty()
+                            && portal_transform.translation.distance(transform.translation)
+                                <= FALLBACK_LINK_RADIUS
+                    })
+                    .map(|(entity, _, _)| entity)
+                    .collect(),
+                explicit: false,
+            }
+        } else {
+            LinkedBeacons {
+                beacons: explicit,
+                explicit: true,
+            }
+        };
+
+        commands.entity(portal).insert(links);
+    }
 }
 
 fn close_portals(
-    mut portals: Query<(&Transform, &mut PortalState), With<Portal>>,
-    beacons: Query<(&Transform, &BeaconState), (With<Beacon>, Without<Portal>)>,
+    mut commands: Commands,
+    mut portals: Query<(Entity, &LinkedBeacons, &mut PortalState), With<Portal>>,
+    beacons: Query<&BeaconState, (With<Beacon>, Without<Portal>)>,
 ) {
-    for (portal_transform, mut portal_state) in portals
+    for (entity, links, mut portal_state) in portals
         .iter_mut()
-        .filter(|(_, state)| **state == PortalState::Open)
+        .filter(|(_, _, state)| **state == PortalState::Open)
     {
-        if beacons
+        // a portal without beacons can't be closed by them.
+        if links.beacons.is_empty() {
+            continue;
+        }
+
+        if links
+            .beacons
             .iter()
-            .filter(|(transform, _)| {
-                portal_transform.translation.distance(transform.translation) <= 30.0
-            })
-            .all(|(_, state)| *state == BeaconState::Online)
+            .all(|beacon| beacons.get(*beacon).is_ok_and(|state| *state == BeaconState::Online))
+        {
+            *portal_state = PortalState::Closing;
+
+            commands
+                .entity(entity)
+                .insert((ClosingTimer::default(), CloseReason::Beacons));
+        }
+    }
+}
+
+/// Starts collapsing portals the player has shot down.
+fn destroy_portals(
+    mut commands: Commands,
+    mut portals: Query<(Entity, &Health, &mut PortalState), (With<Portal>, Changed<Health>)>,
+) {
+    for (entity, health, mut state) in portals.iter_mut() {
+        if *state != PortalState::Open || health.amount > 0.0 {
+            continue;
+        }
+
+        *state = PortalState::Closing;
+
+        commands
+            .entity(entity)
+            .insert((ClosingTimer::default(), CloseReason::Destroyed));
+
+        info!("Portal destroyed!");
+    }
+}
+
+/// Opens portals back up when one of the beacons that closed them goes offline, if their
+/// definition allows it. Portals the player destroyed stay closed.
+fn reopen_portals(
+    mut commands: Commands,
+    mut portals: Query<
+        (
+            Entity,
+            &LinkedBeacons,
+            &PortalDefinition,
+            &CloseReason,
+            &mut PortalState,
+            Option<&mut Health>,
+        ),
+        With<Portal>,
+    >,
+    beacons: Query<&BeaconState, (With<Beacon>, Without<Portal>)>,
+    children: Query<&Children>,
+    mut surfaces: Query<(&Name, &mut Transform), With<Handle<StandardMaterial>>>,
+    mut reopened: EventWriter<PortalReopened>,
+) {
+    for (entity, links, definition, reason, mut state, health) in portals.iter_mut() {
+        if !definition.reopens || *reason != CloseReason::Beacons || *state == PortalState::Open {
+            continue;
+        }
+
+        if links
+            .beacons
+            .iter()
+            .all(|beacon| beacons.get(*beacon).is_ok_and(|state| *state == BeaconState::Online))
         {
-            *portal_state = PortalState::Closed;
+            continue;
+        }
+
+        *state = PortalState::Open;
+
+        if let Some(mut health) = health {
+            health.amount = health.max;
+        }
+
+        commands
+            .entity(entity)
+            .remove::<(ClosingTimer, CloseReason)>();
+
+        scale_portal_surface(entity, 1.0, &children, &mut surfaces);
+
+        reopened.send(PortalReopened(entity));
+
+        info!("Portal reopened!");
+    }
+}
+
+/// Shrinks collapsing portals down to nothing, then closes them for good.
+fn update_closing_portals(
+    time: Res<Time>,
+    mut commands: Commands,
+    mut portals: Query<(Entity, &CloseReason, &mut ClosingTimer, &mut PortalState), With<Portal>>,
+    children: Query<&Children>,
+    mut surfaces: Query<(&Name, &mut Transform), With<Handle<StandardMaterial>>>,
+    mut closed: EventWriter<PortalClosed>,
+) {
+    for (entity, reason, mut timer, mut state) in portals.iter_mut() {
+        timer.tick(time.delta());
+
+        scale_portal_surface(entity, timer.percent_left(), &children, &mut surfaces);
+
+        if timer.just_finished() {
+            *state = PortalState::Closed;
+
+            commands.entity(entity).remove::<ClosingTimer>();
+
+            closed.send(PortalClosed {
+                portal: entity,
+                reason: *reason,
+            });
+        }
+    }
+}
+
+/// Scales the swirling surface of a portal, leaving its frame alone.
+fn scale_portal_surface(
+    portal: Entity,
+    scale: f32,
+    children: &Query<&Children>,
+    surfaces: &mut Query<(&Name, &mut Transform), With<Handle<StandardMaterial>>>,
+) {
+    for descendant in children.iter_descendants(portal) {
+        if let Ok((name, mut transform)) = surfaces.get_mut(descendant) {
+            if name.as_str().contains("Portal") {
+                transform.scale = Vec3::splat(scale);
+            }
+        }
+    }
+}
+
+fn show_portal_links(
+    portals: Query<(&Transform, &LinkedBeacons), With<Portal>>,
+    beacons: Query<(&Transform, &BeaconState), (With<Beacon>, Without<Portal>)>,
+    mut gizmos: Gizmos,
+) {
+    for (portal_transform, links) in portals.iter() {
+        for (transform, state) in links.beacons.iter().filter_map(|beacon| beacons.get(*beacon).ok()) {
+            gizmos.line(
+                transform.translation,
+                portal_transform.translation,
+                state.color(),
+            );
+        }
+    }
+}
+
+#[derive(Component)]
+pub struct PortalLinkDisplay;
+
+fn spawn_portal_ui(mut commands: Commands) {
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    top: Val::Px(5.0),
+                    right: Val::Px(10.0),
+                    ..default()
+                },
+                ..default()
+            },
+            InGame,
+        ))
+        .with_children(|parent| {
+            parent.spawn((TextBundle::default(), PortalLinkDisplay));
+        });
+}
+
+/// Lists each portal along with the state of every beacon linked to it.
+fn update_portal_ui(
+    mut displays: Query<&mut Text, With<PortalLinkDisplay>>,
+    portals: Query<
+        (&LinkedBeacons, &PortalState, Option<&PortalId>, Option<&Health>),
+        With<Portal>,
+    >,
+    beacons: Query<&BeaconState, (With<Beacon>, Without<Portal>)>,
+    server: Res<AssetServer>,
+) {
+    let style = TextStyle {
+        font_size: 20.,
+        color: Color::rgb_u8(56, 193, 235),
+        font: server.load("fonts/motion-control.bold.otf"),
+    };
+
+    for mut text in displays.iter_mut() {
+        text.sections.clear();
+
+        for (index, (links, portal_state, id, health)) in portals.iter().enumerate() {
+            let name = id.map_or(index as u32 + 1, |PortalId(id)| *id);
+
+            let integrity = match (portal_state, health) {
+                (PortalState::Open, Some(health)) => {
+                    format!("{:.0}% ", health.amount / health.max * 100.0)
+                }
+                _ => String::new(),
+            };
+
+            text.sections.push(TextSection::new(
+                format!("PORTAL {name} {integrity}"),
+                TextStyle {
+                    color: portal_state.color(),
+                    ..style.clone()
+                },
+            ));
+
+            for state in links.beacons.iter().filter_map(|beacon| beacons.get(*beacon).ok()) {
+                text.sections.push(TextSection::new(
+                    "# ",
+                    TextStyle {
+                        color: state.color(),
+                        ..style.clone()
+                    },
+                ));
+            }
+
+            text.sections.push(TextSection::new("\n", style.clone()));
         }
     }
 }
@@ -139,15 +746,9 @@ fn change_portal_color(
     for (beacon, state) in portals.iter() {
         for descendant in children.iter_descendants(beacon) {
             if let Ok((_, name)) = mat_query.get(descendant) {
-                info!("{:?}", name.as_str());
                 if name.as_str().contains("Portal") {
                     // <- hard coded, cause why not.
-                    let color = match state {
-                        PortalState::Closed => Color::GREEN,
-                        PortalState::Open => Color::RED,
-                    };
-
-                    let mat = materials.add((color * 7.0).into());
+                    let mat = materials.add((state.color() * 7.0).into());
 
                     commands.entity(descendant).insert(mat);
                 }
@@ -155,3 +756,95 @@ fn change_portal_color(
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn portal_reopens_when_a_linked_beacon_goes_offline() {
+        let mut app = App::new();
+
+        app.add_event::<PortalReopened>()
+            .add_systems(Update, reopen_portals);
+
+        let beacon = app
+            .world
+            .spawn((Beacon::default(), BeaconState::Online))
+            .id();
+
+        let mut health = Health::new(500.0);
+        health.amount = 0.0;
+
+        let portal = app
+            .world
+            .spawn((
+                Portal,
+                LinkedBeacons {
+                    beacons: vec![beacon],
+                    explicit: true,
+                },
+                PortalDefinition {
+                    reopens: true,
+                    ..default()
+                },
+                CloseReason::Beacons,
+                PortalState::Closed,
+                health,
+            ))
+            .id();
+
+        app.update();
+
+        // the beacon's still online, so the portal stays closed.
+        assert_eq!(
+            *app.world.get::<PortalState>(portal).unwrap(),
+            PortalState::Closed
+        );
+
+        *app.world.get_mut::<BeaconState>(beacon).unwrap() = BeaconState::Offline;
+        app.update();
+
+        assert_eq!(
+            *app.world.get::<PortalState>(portal).unwrap(),
+            PortalState::Open
+        );
+        assert!(app.world.get::<CloseReason>(portal).is_none());
+        assert_eq!(app.world.get::<Health>(portal).unwrap().amount, 500.0);
+        assert_eq!(app.world.resource::<Events<PortalReopened>>().len(), 1);
+    }
+
+    #[test]
+    fn portal_stays_closed_unless_it_reopens() {
+        let mut app = App::new();
+
+        app.add_event::<PortalReopened>()
+            .add_systems(Update, reopen_portals);
+
+        let beacon = app
+            .world
+            .spawn((Beacon::default(), BeaconState::Offline))
+            .id();
+
+        let portal = app
+            .world
+            .spawn((
+                Portal,
+                LinkedBeacons {
+                    beacons: vec![beacon],
+                    explicit: true,
+                },
+                PortalDefinition::default(),
+                CloseReason::Beacons,
+                PortalState::Closed,
+            ))
+            .id();
+
+        app.update();
+
+        assert_eq!(
+            *app.world.get::<PortalState>(portal).unwrap(),
+            PortalState::Closed
+        );
+    }
+}
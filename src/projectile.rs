@@ -3,7 +3,7 @@ use std::f32::consts::PI;
 use bevy::prelude::*;
 use bevy_xpbd_3d::{prelude::*, SubstepSchedule, SubstepSet};
 
-use crate::{controller::CharacterController, health::UpdateHealth, Enemy, GameState};
+use crate::{controller::CharacterController, health::{DamageEvent, DamageKind}, portal::{Portal, Projector}, Enemy, GameState};
 
 #[derive(Component)]
 pub struct Projectile;
@@ -14,10 +14,15 @@ pub struct Damage(pub f32);
 #[derive(Component)]
 pub struct Speed(pub f32);
 
+/// Whatever fired a projectile, credited with the damage it deals.
+#[derive(Component)]
+pub struct Shooter(pub Entity);
+
 #[derive(Bundle)]
 pub struct ProjectileBundle {
     pub damage: Damage,
     pub speed: Speed,
+    pub shooter: Shooter,
 }
 
 pub struct ProjectilePlugin;
@@ -31,6 +36,7 @@ impl Plugin for ProjectilePlugin {
                 .run_if(in_state(GameState::Playing)),
         )
         .add_systems(OnEnter(GameState::GameOver), crate::cleanup::<Projectile>)
+        .add_systems(OnEnter(GameState::MainMenu), crate::cleanup::<Projectile>)
         .add_systems(
             SubstepSchedule,
             handle_projectile_collisions.in_set(SubstepSet::SolveUserConstraints),
@@ -88,6 +94,7 @@ fn spawn_projectile(
                 ProjectileBundle {
                     damage: Damage(115.0),
                     speed: Speed(30.0),
+                    shooter: Shooter(entity),
                 },
                 Collider::capsule(0.25, 0.1),
                 RigidBody::Kinematic,
@@ -109,6 +116,41 @@ fn spawn_projectile(
     }
 }
 
+/// Spawns a bolt fired by `shooter` that flies along the local -Y axis of `transform`.
+pub fn spawn_bolt(
+    commands: &mut Commands,
+    meshes: &mut Assets<Mesh>,
+    materials: &mut Assets<StandardMaterial>,
+    shooter: Entity,
+    transform: Transform,
+    damage: f32,
+    speed: f32,
+) {
+    commands.spawn((
+        PbrBundle {
+            mesh: meshes.add(
+                shape::Capsule {
+                    depth: 0.9,
+                    radius: 0.05,
+                    ..default()
+                }
+                .into(),
+            ),
+            material: materials.add(Color::rgb(10.0, 0.0, 0.0).into()),
+            transform,
+            ..default()
+        },
+        Projectile,
+        ProjectileBundle {
+            damage: Damage(damage),
+            speed: Speed(speed),
+            shooter: Shooter(shooter),
+        },
+        Collider::capsule(0.25, 0.1),
+        RigidBody::Kinematic,
+    ));
+}
+
 fn update_projectiles(
     time: Res<Time>,
     mut projectiles: Query<(&Speed, &mut Transform), With<Projectile>>,
@@ -121,10 +163,10 @@ fn update_projectiles(
 
 fn handle_projectile_collisions(
     mut commands: Commands,
-    projectiles: Query<(Entity, &Speed, &Damage), With<Projectile>>,
-    enemies: Query<Entity, With<Enemy>>,
+    projectiles: Query<(Entity, &Transform, &Damage, &Shooter), With<Projectile>>,
+    targets: Query<Entity, Or<(With<Enemy>, With<Portal>, With<Projector>)>>,
     mut collision_events: EventReader<CollisionStarted>,
-    mut update_health_events: EventWriter<UpdateHealth>,
+    mut damage_events: EventWriter<DamageEvent>,
 ) {
     for CollisionStarted(a, b) in collision_events.read() {
         let projectile = if projectiles.get(*a).is_ok() {
@@ -135,19 +177,23 @@ fn handle_projectile_collisions(
             continue;
         };
 
-        let enemy = if enemies.get(*a).is_ok() {
+        let target = if targets.get(*a).is_ok() {
             *a
-        } else if enemies.get(*b).is_ok() {
+        } else if targets.get(*b).is_ok() {
             *b
         } else {
             continue;
         };
 
-        //println!("collision between {projectile:?} and {enemy:?}");
+        //println!("collision between {projectile:?} and {target:?}");
         commands.entity(projectile).despawn_recursive();
 
-        let (_, _, damage) = projectiles.get(projectile).unwrap();
+        let (_, transform, damage, Shooter(shooter)) = projectiles.get(projectile).unwrap();
 
-        update_health_events.send(UpdateHealth(enemy, -damage.0));
+        damage_events.send(
+            DamageEvent::new(target, damage.0, DamageKind::Energy)
+                .with_source(*shooter)
+                .with_point(transform.translation),
+        );
     }
 }
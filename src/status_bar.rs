@@ -0,0 +1,129 @@
+use bevy::prelude::*;
+use bevy_mod_billboard::BillboardTextBundle;
+
+use crate::{beacon::Beacon, health::Health, portal::Portal, Enemy, GameState};
+
+/// Shows health bars above enemies, beacons and portals for a little while after they're hurt.
+pub struct StatusBarPlugin;
+
+impl Plugin for StatusBarPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            Update,
+            (attach_status_bars, update_status_bars)
+                .chain()
+                .run_if(in_state(GameState::Playing)),
+        );
+    }
+}
+
+/// How many segments a status bar is made of.
+const SEGMENTS: usize = 20;
+
+/// How long a status bar stays up after the last change in health, in seconds.
+const SHOW_TIME: f32 = 3.0;
+
+/// How long a status bar takes to fade out once it's been up for [`SHOW_TIME`], in seconds.
+const FADE_TIME: f32 = 1.0;
+
+/// A health bar floating above its parent.
+#[derive(Component)]
+pub struct StatusBar(pub Timer);
+
+/// Marks something that currently has a [`StatusBar`] showing.
+#[derive(Component)]
+pub struct HasStatusBar;
+
+fn attach_status_bars(
+    mut commands: Commands,
+    targets: Query<
+        (Entity, &Health, Option<&Enemy>, Option<&Beacon>),
+        (
+            Changed<Health>,
+            Without<HasStatusBar>,
+            Or<(With<Enemy>, With<Beacon>, With<Portal>)>,
+        ),
+    >,
+    server: Res<AssetServer>,
+) {
+    for (entity, health, enemy, beacon) in targets.iter() {
+        if health.amount <= 0.0 || health.amount >= health.max {
+            continue;
+        }
+
+        // sit just above the top of whatever the bar is for.
+        let height = if enemy.is_some() {
+            1.8
+        } else if beacon.is_some() {
+            3.6
+        } else {
+            6.0
+        };
+
+        let style = TextStyle {
+            font_size: 40.,
+            color: Color::NONE,
+            font: server.load("fonts/motion-control.bold.otf"),
+        };
+
+        commands
+            .entity(entity)
+            .insert(HasStatusBar)
+            .with_children(|parent| {
+                parent.spawn((
+                    BillboardTextBundle {
+                        transform: Transform::from_xyz(0., height, 0.)
+                            .with_scale(Vec3::splat(0.0085)),
+                        text: Text::from_sections([
+                            TextSection::new("", style.clone()),
+                            TextSection::new("", style),
+                        ])
+                        .with_alignment(TextAlignment::Center),
+                        ..default()
+                    },
+                    StatusBar(Timer::from_seconds(SHOW_TIME + FADE_TIME, TimerMode::Once)),
+                ));
+            });
+    }
+}
+
+fn update_status_bars(
+    time: Res<Time>,
+    mut commands: Commands,
+    targets: Query<Ref<Health>, With<HasStatusBar>>,
+    mut bars: Query<(Entity, &Parent, &mut StatusBar, &mut Text)>,
+) {
+    for (bar, parent, mut status_bar, mut text) in bars.iter_mut() {
+        let Ok(health) = targets.get(parent.get()) else {
+            commands.entity(bar).despawn_recursive();
+            continue;
+        };
+
+        if health.is_changed() {
+            status_bar.0.reset();
+        }
+
+        if health.amount <= 0.0 || status_bar.0.tick(time.delta()).finished() {
+            commands.entity(parent.get()).remove::<HasStatusBar>();
+            commands.entity(bar).despawn_recursive();
+            continue;
+        }
+
+        let fraction = (health.amount / health.max).clamp(0.0, 1.0);
+        let filled = (fraction * SEGMENTS as f32).ceil() as usize;
+
+        let alpha = ((SHOW_TIME + FADE_TIME - status_bar.0.elapsed_secs()) / FADE_TIME).min(1.0);
+
+        // green when healthy, through yellow to red when nearly dead.
+        let color = if fraction > 0.5 {
+            Color::rgb(2.0 * (1.0 - fraction), 1.0, 0.0)
+        } else {
+            Color::rgb(1.0, 2.0 * fraction, 0.0)
+        };
+
+        text.sections[0].value = "|".repeat(filled);
+        text.sections[0].style.color = color.with_a(alpha);
+        text.sections[1].value = "|".repeat(SEGMENTS - filled);
+        text.sections[1].style.color = Color::DARK_GRAY.with_a(alpha * 0.6);
+    }
+}
@@ -0,0 +1,271 @@
+use bevy::prelude::*;
+use bevy_xpbd_3d::prelude::{SpatialQuery, SpatialQueryFilter};
+use rand::Rng;
+
+/// How strongly each steering behavior pulls on an enemy.
+///
+/// Each behavior produces a direction of at most unit length. They are scaled by these weights,
+/// summed up and clamped back down to unit length before being turned into an acceleration.
+#[derive(Component, Clone, Copy, Debug)]
+pub struct SteeringWeights {
+    pub seek: f32,
+    pub arrive: f32,
+    pub flee: f32,
+    pub wander: f32,
+    pub pursue: f32,
+    pub avoid: f32,
+}
+
+impl SteeringWeights {
+    /// Regular creeps meander between waypoints and keep their distance once they've found the player.
+    pub const CREEP: Self = Self {
+        seek: 1.0,
+        arrive: 1.0,
+        flee: 1.0,
+        wander: 0.35,
+        pursue: 1.0,
+        avoid: 1.5,
+    };
+
+    /// Red creeps barely wander and lead the player much more aggressively.
+    pub const RED_CREEP: Self = Self {
+        seek: 1.0,
+        arrive: 1.0,
+        flee: 0.5,
+        wander: 0.1,
+        pursue: 1.5,
+        avoid: 1.5,
+    };
+}
+
+impl Default for SteeringWeights {
+    fn default() -> Self {
+        Self::CREEP
+    }
+}
+
+/// How quickly an enemy can change its velocity, in units per second squared.
+#[derive(Component)]
+pub struct MaxAcceleration(pub f32);
+
+/// The state of an enemy's wandering.
+#[derive(Component)]
+pub struct Wander {
+    /// The heading the enemy is currently wandering in, in radians.
+    pub heading: f32,
+    /// How far the heading can drift each second, in radians.
+    pub jitter: f32,
+}
+
+impl Default for Wander {
+    fn default() -> Self {
+        Self {
+            heading: rand::thread_rng().gen_range(0.0..std::f32::consts::TAU),
+            jitter: 3.0,
+        }
+    }
+}
+
+/// Accumulates weighted steering behaviors into a single acceleration.
+#[derive(Default, Clone, Copy, Debug)]
+pub struct Steering(Vec3);
+
+impl Steering {
+    pub fn add(&mut self, direction: Vec3, weight: f32) -> &mut Self {
+        self.0 += direction * weight;
+        self
+    }
+
+    /// The combined acceleration, scaled by the enemy's maximum acceleration.
+    pub fn acceleration(&self, max_acceleration: f32) -> Vec3 {
+        self.0.clamp_length_max(1.0) * max_acceleration
+    }
+}
+
+fn flatten(v: Vec3) -> Vec3 {
+    Vec3::new(v.x, 0.0, v.z)
+}
+
+/// Heads straight for `target`.
+pub fn seek(position: Vec3, target: Vec3) -> Vec3 {
+    flatten(target - position).normalize_or_zero()
+}
+
+/// Heads straight away from `threat`.
+pub fn flee(position: Vec3, threat: Vec3) -> Vec3 {
+    -seek(position, threat)
+}
+
+/// Heads for `target`, easing off once within `slowing_radius` of it.
+pub fn arrive(position: Vec3, target: Vec3, slowing_radius: f32) -> Vec3 {
+    let offset = flatten(target - position);
+    let distance = offset.length();
+
+    if distance <= f32::EPSILON {
+        return Vec3::ZERO;
+    }
+
+    offset / distance * (distance / slowing_radius).min(1.0)
+}
+
+/// Heads for where `target` will be if it keeps moving at `target_velocity`, assuming the
+/// pursuer closes in at `speed`. The lead is capped at `max_lead` seconds so far away targets
+/// aren't predicted wildly.
+pub fn pursue(
+    position: Vec3,
+    target: Vec3,
+    target_velocity: Vec3,
+    speed: f32,
+    max_lead: f32,
+) -> Vec3 {
+    let lead = if speed > f32::EPSILON {
+        (position.distance(target) / speed).min(max_lead)
+    } else {
+        0.0
+    };
+
+    seek(position, target + target_velocity * lead)
+}
+
+/// Pushes `entity` at `position` away from any of its `neighbours` within `radius`, harder the
+/// closer they are.
+pub fn separation(
+    entity: Entity,
+    position: Vec3,
+    neighbours: impl IntoIterator<Item = (Entity, Vec3)>,
+    radius: f32,
+) -> Vec3 {
+    let mut push = Vec3::ZERO;
+
+    for (other, other_pos) in neighbours.into_iter().filter(|(other, _)| *other != entity) {
+        let offset = flatten(position - other_pos);
+        let dist = offset.length();
+
+        if dist >= radius {
+            continue;
+        }
+
+        // enemies on top of each other have no direction to push apart in, so push them
+        // in opposite directions along an arbitrary axis.
+        let direction = if dist > f32::EPSILON {
+            offset / dist
+        } else if entity.index() > other.index() {
+            Vec3::X
+        } else {
+            Vec3::NEG_X
+        };
+
+        push += direction * (1.0 - dist / radius);
+    }
+
+    push
+}
+
+/// Drifts the wander heading randomly and heads in that direction.
+pub fn wander(state: &mut Wander, delta_seconds: f32, rng: &mut impl Rng) -> Vec3 {
+    state.heading += rng.gen_range(-1.0..1.0) * state.jitter * delta_seconds;
+
+    Vec3::new(state.heading.cos(), 0.0, state.heading.sin())
+}
+
+/// Looks `look_ahead` units along `velocity` and steers away from the first obstacle found,
+/// harder the closer it is.
+pub fn avoid_obstacles(
+    spatial_query: &SpatialQuery,
+    position: Vec3,
+    velocity: Vec3,
+    look_ahead: f32,
+    filter: SpatialQueryFilter,
+) -> Vec3 {
+    let direction = flatten(velocity).normalize_or_zero();
+
+    if direction == Vec3::ZERO {
+        return Vec3::ZERO;
+    }
+
+    let Some(hit) = spatial_query.cast_ray(position, direction, look_ahead, true, filter) else {
+        return Vec3::ZERO;
+    };
+
+    // Steer along the wall rather than straight back, so the enemy keeps making progress.
+    let mut normal = flatten(hit.normal).normalize_or_zero();
+    if normal.dot(direction) > 0.0 {
+        normal = -normal;
+    }
+
+    let along_wall = direction - normal * direction.dot(normal);
+    let urgency = 1.0 - hit.time_of_impact / look_ahead;
+
+    (normal + along_wall).normalize_or_zero() * urgency
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn seek_heads_for_the_target_on_the_ground_plane() {
+        let direction = seek(Vec3::ZERO, Vec3::new(3.0, 10.0, 4.0));
+
+        assert!((direction - Vec3::new(0.6, 0.0, 0.8)).length() < 1e-5);
+        assert_eq!(seek(Vec3::ONE, Vec3::ONE), Vec3::ZERO);
+    }
+
+    #[test]
+    fn flee_heads_away_from_the_threat() {
+        assert_eq!(flee(Vec3::ZERO, Vec3::X * 5.0), Vec3::NEG_X);
+    }
+
+    #[test]
+    fn arrive_eases_off_near_the_target() {
+        assert_eq!(arrive(Vec3::ZERO, Vec3::X * 10.0, 5.0), Vec3::X);
+        assert!((arrive(Vec3::ZERO, Vec3::X * 2.5, 5.0) - Vec3::X * 0.5).length() < 1e-5);
+        assert_eq!(arrive(Vec3::ZERO, Vec3::ZERO, 5.0), Vec3::ZERO);
+    }
+
+    #[test]
+    fn separation_pushes_away_from_close_neighbours_only() {
+        let entity = Entity::from_raw(0);
+        let near = Entity::from_raw(1);
+        let far = Entity::from_raw(2);
+
+        let push = separation(
+            entity,
+            Vec3::ZERO,
+            [(entity, Vec3::ZERO), (near, Vec3::X), (far, Vec3::Z * 10.0)],
+            2.0,
+        );
+
+        assert!((push - Vec3::NEG_X * 0.5).length() < 1e-5);
+    }
+
+    #[test]
+    fn separation_splits_enemies_on_top_of_each_other() {
+        let a = Entity::from_raw(0);
+        let b = Entity::from_raw(1);
+
+        let push_a = separation(a, Vec3::ZERO, [(b, Vec3::ZERO)], 2.0);
+        let push_b = separation(b, Vec3::ZERO, [(a, Vec3::ZERO)], 2.0);
+
+        assert_eq!(push_a, -push_b);
+        assert_eq!(push_a.length(), 1.0);
+    }
+
+    #[test]
+    fn steering_blends_weighted_behaviors() {
+        let mut steer = Steering::default();
+        steer.add(Vec3::X, 0.25).add(Vec3::Z, 0.5);
+
+        assert!((steer.acceleration(10.0) - Vec3::new(2.5, 0.0, 5.0)).length() < 1e-5);
+    }
+
+    #[test]
+    fn steering_clamps_the_blend_to_max_acceleration() {
+        let mut steer = Steering::default();
+        steer
+            .add(Vec3::X, SteeringWeights::RED_CREEP.pursue)
+            .add(Vec3::X, SteeringWeights::RED_CREEP.avoid);
+
+        assert!((steer.acceleration(40.0) - Vec3::X * 40.0).length() < 1e-5);
+    }
+}
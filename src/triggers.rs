@@ -0,0 +1,255 @@
+use bevy::{gltf::GltfExtras, prelude::*};
+use bevy_xpbd_3d::prelude::CollisionStarted;
+use rand::Rng;
+use serde::Deserialize;
+
+use crate::{
+    controller::CharacterController,
+    door::{Door, DoorState},
+    portal::{spawn_creep, CreepKind, Dormant, Portal, PortalId},
+    GameState, InGame,
+};
+
+/// Runs the scripted actions of level triggers as the player walks into them.
+pub struct TriggerPlugin;
+
+impl Plugin for TriggerPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<TriggerFired>()
+            .add_event::<CheckpointReached>()
+            .add_systems(
+                Update,
+                (fire_triggers, run_trigger_actions, hide_trigger_messages)
+                    .chain()
+                    .run_if(in_state(GameState::Playing)),
+            );
+    }
+}
+
+/// A volume that runs `actions` when the player walks into it. Levels set it up through the extras
+/// of a node named `Trigger`, e.g.
+/// `{ "size": [6.0, 3.0, 6.0], "actions": [{ "type": "show_text", "text": "Find the beacons" }] }`.
+#[derive(Component, Deserialize, Clone, Debug)]
+#[serde(default)]
+pub struct Trigger {
+    /// The size of the box the player has to walk into.
+    pub size: [f32; 3],
+    /// Whether the trigger goes away once fired, rather than firing every time it's entered.
+    pub once: bool,
+    pub actions: Vec<TriggerAction>,
+}
+
+impl Default for Trigger {
+    fn default() -> Self {
+        Self {
+            size: [3.0, 3.0, 3.0],
+            once: true,
+            actions: Vec::new(),
+        }
+    }
+}
+
+impl Trigger {
+    /// Reads a trigger from the extras of its level node. Triggers without any extras don't do
+    /// anything, so they're left out.
+    pub fn from_extras(extras: Option<&GltfExtras>) -> Option<Self> {
+        let extras = extras?;
+
+        let mut trigger: Self = match serde_json::from_str(&extras.value) {
+            Ok(trigger) => trigger,
+            Err(err) => {
+                warn!("invalid trigger {:?}: {err}", extras.value);
+                return None;
+            }
+        };
+
+        // text times end up as timer durations, which panic on anything negative or NaN.
+        for action in trigger.actions.iter_mut() {
+            if let TriggerAction::ShowText { seconds, .. } = action {
+                if !(seconds.is_finite() && *seconds >= 0.0) {
+                    warn!("invalid trigger text time {seconds}, using the default");
+                    *seconds = default_text_seconds();
+                }
+            }
+        }
+
+        Some(trigger)
+    }
+}
+
+/// Something a trigger does when fired.
+#[derive(Deserialize, Clone, Debug)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum TriggerAction {
+    /// Spawns `count` creeps of the given `kind` around `at`, or around the trigger if left out.
+    SpawnWave {
+        count: usize,
+        #[serde(default)]
+        at: Option<[f32; 3]>,
+        #[serde(default)]
+        kind: CreepKind,
+    },
+    /// Opens the door whose node is called `door`, even if it's locked.
+    OpenDoor { door: String },
+    /// Shows `text` to the player for `seconds`.
+    ShowText {
+        text: String,
+        #[serde(default = "default_text_seconds")]
+        seconds: f32,
+    },
+    /// Plays the sound at the asset path `sound`.
+    PlaySound { sound: String },
+    /// Lets a dormant portal start spawning enemies.
+    EnablePortal { portal: u32 },
+    /// Makes the trigger the place the player comes back to after dying.
+    SetCheckpoint,
+}
+
+fn default_text_seconds() -> f32 {
+    4.0
+}
+
+/// Sent for every action of a trigger when the player walks into it.
+#[derive(Event)]
+pub struct TriggerFired {
+    pub trigger: Entity,
+    /// Where the trigger is.
+    pub position: Vec3,
+    pub action: TriggerAction,
+}
+
+/// Sent when the player walks into a trigger with a [`TriggerAction::SetCheckpoint`].
+#[derive(Event)]
+pub struct CheckpointReached(pub Vec3);
+
+/// A message shown by a [`TriggerAction::ShowText`], removed once its timer runs out.
+#[derive(Component)]
+pub struct TriggerMessage(pub Timer);
+
+fn fire_triggers(
+    mut commands: Commands,
+    mut collisions: EventReader<CollisionStarted>,
+    character: Query<Entity, With<CharacterController>>,
+    triggers: Query<(&Trigger, &Transform)>,
+    mut fired: EventWriter<TriggerFired>,
+) {
+    let Ok(character_entity) = character.get_single() else {
+        return;
+    };
+
+    for CollisionStarted(a, b) in collisions.read() {
+        let entity = if *a == character_entity {
+            *b
+        } else if *b == character_entity {
+            *a
+        } else {
+            continue;
+        };
+
+        let Ok((trigger, transform)) = triggers.get(entity) else {
+            continue;
+        };
+
+        for action in trigger.actions.iter() {
+            fired.send(TriggerFired {
+                trigger: entity,
+                position: transform.translation,
+                action: action.clone(),
+            });
+        }
+
+        if trigger.once {
+            commands.entity(entity).despawn_recursive();
+        }
+    }
+}
+
+fn run_trigger_actions(
+    mut commands: Commands,
+    mut fired: EventReader<TriggerFired>,
+    mut doors: Query<(&Name, &mut DoorState), With<Door>>,
+    portals: Query<(Entity, &PortalId), (With<Portal>, With<Dormant>)>,
+    mut checkpoints: EventWriter<CheckpointReached>,
+    server: Res<AssetServer>,
+) {
+    for TriggerFired {
+        trigger,
+        position,
+        action,
+    } in fired.read()
+    {
+        info!("trigger {trigger:?} fired {action:?}");
+
+        match action {
+            TriggerAction::SpawnWave { count, at, kind } => {
+                let center = at.map_or(*position, Vec3::from_array);
+                let mut rng = rand::thread_rng();
+
+                for _ in 0..*count {
+                    let offset = Vec3::new(rng.gen_range(-3.0..3.0), 1.5, rng.gen_range(-3.0..3.0));
+
+                    spawn_creep(&mut commands, &server, *kind, center + offset);
+                }
+            }
+            TriggerAction::OpenDoor { door } => {
+                let Some((_, mut state)) = doors
+                    .iter_mut()
+                    .find(|(name, _)| name.as_str() == door.as_str())
+                else {
+                    warn!("trigger {trigger:?} opens door {door:?}, which doesn't exist");
+                    continue;
+                };
+
+                if matches!(*state, DoorState::Closed | DoorState::Locked) {
+                    *state = DoorState::Opening;
+                }
+            }
+            TriggerAction::ShowText { text, seconds } => {
+                commands.spawn((
+                    TextBundle::from_section(
+                        text.clone(),
+                        TextStyle {
+                            font_size: 24.,
+                            color: Color::rgb_u8(56, 193, 235),
+                            font: server.load("fonts/motion-control.bold.otf"),
+                        },
+                    )
+                    .with_style(Style {
+                        position_type: PositionType::Absolute,
+                        bottom: Val::Px(60.0),
+                        left: Val::Px(10.0),
+                        ..default()
+                    }),
+                    TriggerMessage(Timer::from_seconds(*seconds, TimerMode::Once)),
+                    InGame,
+                ));
+            }
+            TriggerAction::PlaySound { sound } => {
+                commands.spawn(AudioBundle {
+                    source: server.load(sound.clone()),
+                    settings: PlaybackSettings::DESPAWN,
+                });
+            }
+            TriggerAction::EnablePortal { portal } => {
+                for (entity, _) in portals.iter().filter(|(_, PortalId(id))| id == portal) {
+                    commands.entity(entity).remove::<Dormant>();
+                }
+            }
+            TriggerAction::SetCheckpoint => {
+                checkpoints.send(CheckpointReached(*position));
+            }
+        }
+    }
+}
+
+fn hide_trigger_messages(
+    time: Res<Time>,
+    mut commands: Commands,
+    mut messages: Query<(Entity, &mut TriggerMessage)>,
+) {
+    for (entity, mut message) in messages.iter_mut() {
+        if message.0.tick(time.delta()).finished() {
+            commands.entity(entity).despawn_recursive();
+        }
+    }
+}
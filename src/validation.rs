@@ -0,0 +1,70 @@
+use bevy::{prelude::*, utils::HashMap};
+
+use crate::{
+    beacon::Beacon,
+    levels::Levels,
+    portal::{link_portals, LinkedBeacons, Portal, PortalId},
+    GameState,
+};
+
+/// Checks each level as it's loaded and logs anything that would make it unplayable, along with
+/// how its beacons and portals are linked together.
+pub struct ValidationPlugin;
+
+impl Plugin for ValidationPlugin {
+    fn build(&self, app: &mut App) {
+        // the links are inserted with commands, so they have to be applied before checking them.
+        app.add_systems(
+            OnEnter(GameState::BeginGame),
+            (apply_deferred, validate_portal_links)
+                .chain()
+                .after(link_portals),
+        );
+    }
+}
+
+fn validate_portal_links(
+    levels: Res<Levels>,
+    portals: Query<(Entity, Option<&PortalId>, &LinkedBeacons), With<Portal>>,
+    beacons: Query<(Entity, &Beacon)>,
+) {
+    let level = &levels.data[levels.current_level].name;
+
+    let mut ids = HashMap::new();
+
+    for (entity, id, _) in portals.iter() {
+        if let Some(PortalId(id)) = id {
+            if let Some(other) = ids.insert(*id, entity) {
+                warn!("{level}: portals {other:?} and {entity:?} share the id {id}");
+            }
+        }
+    }
+
+    for (entity, beacon) in beacons.iter() {
+        for id in beacon.portals.iter().filter(|id| !ids.contains_key(*id)) {
+            warn!("{level}: beacon {entity:?} is linked to portal {id}, which doesn't exist");
+        }
+
+        if !portals
+            .iter()
+            .any(|(_, _, links)| links.beacons.contains(&entity))
+        {
+            warn!("{level}: beacon {entity:?} isn't linked to any portal");
+        }
+    }
+
+    for (entity, id, links) in portals.iter() {
+        let name = id.map_or(format!("{entity:?}"), |PortalId(id)| id.to_string());
+
+        if links.beacons.is_empty() {
+            warn!("{level}: portal {name} has no beacons linked to it and can never be closed");
+        } else if !links.explicit {
+            warn!(
+                "{level}: portal {name} isn't linked to any beacons, so it falls back to the ones \
+                 nearby"
+            );
+        }
+
+        info!("{level}: portal {name} <- beacons {:?}", links.beacons);
+    }
+}
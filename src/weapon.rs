@@ -1,11 +1,11 @@
 use std::f32::consts::PI;
 
 use bevy::prelude::*;
-use bevy_xpbd_3d::prelude::{Collider, RigidBody};
 
 use crate::{
     controller::CharacterController,
-    projectile::{Damage, Projectile, ProjectileBundle, Speed, Weapon},
+    perception::Noise,
+    projectile::{spawn_bolt, Damage, Speed, Weapon},
     GameState,
 };
 
@@ -24,7 +24,39 @@ pub struct AutoFire;
 #[derive(Component)]
 pub struct FiringRate(pub f32);
 
-//pub struct Ammo;
+/// The rounds left in a weapon. Weapons without it never run dry.
+#[derive(Component)]
+pub struct Ammo {
+    pub current: u32,
+    pub max: u32,
+}
+
+impl Ammo {
+    pub fn new(max: u32) -> Self {
+        Self { current: max, max }
+    }
+
+    pub fn refill(&mut self) {
+        self.current = self.max;
+    }
+
+    /// Uses up a round, returning whether there was one to use.
+    pub fn take(&mut self) -> bool {
+        if self.current == 0 {
+            return false;
+        }
+
+        self.current -= 1;
+        true
+    }
+}
+
+/// Multiplies the damage of every shot fired by a character.
+#[derive(Component)]
+pub struct DamageBuff(pub f32);
+
+/// How far away enemies can hear a shot being fired.
+const GUNFIRE_NOISE_RADIUS: f32 = 30.0;
 
 #[derive(Component)]
 pub struct AutoFireTimer(pub Timer);
@@ -32,7 +64,7 @@ pub struct AutoFireTimer(pub Timer);
 fn fire_weapon(
     time: Res<Time>,
     mut commands: Commands,
-    inactive_weapons: Query<
+    mut inactive_weapons: Query<
         (
             Entity,
             &Transform,
@@ -40,6 +72,7 @@ fn fire_weapon(
             &Damage,
             &Speed,
             Option<&AutoFire>,
+            Option<&mut Ammo>,
         ),
         (With<Weapon>, Without<AutoFireTimer>),
     >,
@@ -52,54 +85,50 @@ fn fire_weapon(
             &Speed,
             &mut AutoFireTimer,
             Option<&AutoFire>,
+            Option<&mut Ammo>,
         ),
         With<Weapon>,
     >,
     inputs: Res<Input<MouseButton>>,
     mut meshes: ResMut<Assets<Mesh>>,
     mut materials: ResMut<Assets<StandardMaterial>>,
-    controllers: Query<(Entity, &Transform), With<CharacterController>>,
+    controllers: Query<(Entity, &Transform, Option<&DamageBuff>), With<CharacterController>>,
     asset_server: Res<AssetServer>,
+    mut noises: EventWriter<Noise>,
 ) {
-    let (_, transform) = controllers.single();
+    let Ok((shooter, transform, buff)) = controllers.get_single() else {
+        return;
+    };
+    let multiplier = buff.map_or(1.0, |buff| buff.0);
 
     if inputs.just_pressed(MouseButton::Left) {
-        for (entity, weapon_transform, firing_rate, damage, speed, _) in inactive_weapons
-            .iter()
-            .filter(|(_, _, _, _, _, can_auto_fire)| can_auto_fire.is_some())
+        for (entity, weapon_transform, firing_rate, damage, speed, _, ammo) in inactive_weapons
+            .iter_mut()
+            .filter(|(_, _, _, _, _, can_auto_fire, _)| can_auto_fire.is_some())
         {
             let mut timer = Timer::from_seconds(60.0 / firing_rate.0, TimerMode::Repeating);
 
             commands.entity(entity).insert(AutoFireTimer(timer));
 
+            if ammo.is_some_and(|mut ammo| !ammo.take()) {
+                continue;
+            }
+
             let mut new_transform = *transform;
 
             new_transform.translation = new_transform
                 .transform_point(weapon_transform.translation + Vec3::new(0.0, 0.0, -1.0)); // + Vec3::new(3.0, 0.0, -1.0));
             new_transform.rotate_local_x(PI / 2.0);
 
-            commands.spawn((
-                PbrBundle {
-                    mesh: meshes.add(
-                        shape::Capsule {
-                            depth: 0.9,
-                            radius: 0.05,
-                            ..default()
-                        }
-                        .into(),
-                    ),
-                    material: materials.add(Color::rgb(10.0, 0.0, 0.0).into()),
-                    transform: new_transform,
-                    ..default()
-                },
-                Projectile,
-                ProjectileBundle {
-                    damage: Damage(damage.0),
-                    speed: Speed(speed.0),
-                },
-                Collider::capsule(0.25, 0.1),
-                RigidBody::Kinematic,
-            ));
+            spawn_bolt(
+                &mut commands,
+                &mut meshes,
+                &mut materials,
+                shooter,
+                new_transform,
+                damage.0 * multiplier,
+                speed.0,
+            );
 
             commands.spawn(AudioBundle {
                 settings: PlaybackSettings {
@@ -113,10 +142,15 @@ fn fire_weapon(
                 source: asset_server.load("audio/laser-zap.ogg"),
                 ..default()
             });
+
+            noises.send(Noise {
+                position: transform.translation,
+                radius: GUNFIRE_NOISE_RADIUS,
+            });
         }
     }
 
-    for (entity, weapon_transform, FiringRate(rate_of_fire), speed, damage, mut timer, auto_fire) in
+    for (entity, weapon_transform, FiringRate(rate_of_fire), damage, speed, mut timer, auto_fire, mut ammo) in
         active_weapons.iter_mut()
     {
         if inputs.just_released(MouseButton::Left) {
@@ -124,6 +158,10 @@ fn fire_weapon(
         }
 
         for _ in 0..timer.0.tick(time.delta()).times_finished_this_tick() {
+            if ammo.as_mut().is_some_and(|ammo| !ammo.take()) {
+                break;
+            }
+
             println!("Fire!");
             let mut new_transform = *transform;
 
@@ -131,28 +169,15 @@ fn fire_weapon(
                 .transform_point(weapon_transform.translation + Vec3::new(0.0, 0.0, -1.0)); // + Vec3::new(3.0, 0.0, -1.0));
             new_transform.rotate_local_x(PI / 2.0);
 
-            commands.spawn((
-                PbrBundle {
-                    mesh: meshes.add(
-                        shape::Capsule {
-                            depth: 0.9,
-                            radius: 0.05,
-                            ..default()
-                        }
-                        .into(),
-                    ),
-                    material: materials.add(Color::rgb(10.0, 0.0, 0.0).into()),
-                    transform: new_transform,
-                    ..default()
-                },
-                Projectile,
-                ProjectileBundle {
-                    damage: Damage(damage.0),
-                    speed: Speed(speed.0),
-                },
-                Collider::capsule(0.25, 0.1),
-                RigidBody::Kinematic,
-            ));
+            spawn_bolt(
+                &mut commands,
+                &mut meshes,
+                &mut materials,
+                shooter,
+                new_transform,
+                damage.0 * multiplier,
+                speed.0,
+            );
 
             commands.spawn(AudioBundle {
                 settings: PlaybackSettings {
@@ -166,6 +191,11 @@ fn fire_weapon(
                 source: asset_server.load("audio/laser-zap.ogg"),
                 ..default()
             });
+
+            noises.send(Noise {
+                position: transform.translation,
+                radius: GUNFIRE_NOISE_RADIUS,
+            });
         }
     }
 }